@@ -1,60 +1,320 @@
 use anyhow::{Context, Result};
-use clap::Clap;
+use clap::{Args, Parser, Subcommand};
 use pmd_code_table::CodeTable;
-use pmd_message::MessageBin;
+use pmd_message::{
+    segment_for_translation, Decoder, LayoutRegionKind, MessageBin, MessageBinReadOptions, Segment,
+};
+use serde::{Deserialize, Serialize};
 use std::{
-    fs::File,
-    io::{BufReader, BufWriter},
-    path::PathBuf,
+    collections::{BTreeMap, HashMap},
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Cursor, Write},
+    path::{Path, PathBuf},
+    process,
 };
 
-#[derive(Clap)]
+#[derive(Parser)]
 /// messagetool allow to extract "messagebin" file, used in 3ds pokemon mystery dungeon
 struct Opts {
     #[clap(subcommand)]
     subcmd: SubCommand,
 }
 
-#[derive(Clap)]
+#[derive(Subcommand)]
 enum SubCommand {
     /// decode, then encode a messagebin file
     Reencode(ReencodeParameter),
+    /// print the differences between two messagebin files. Like diff(1), exit with the status 0 if they are identical, 1 if they differ, and 2 on error.
+    Diff(DiffParameter),
+    /// split a messagebin file into one messagebin file per unknown value, named after that value, and write the order of the messages to order.txt
+    ExportByUnk(ExportByUnkParameter),
+    /// merge all the messagebin files of a folder (like those created by export-by-unk) into a single one, in the order of order.txt if present,
+    /// then by increasing unknown value
+    ImportByUnk(ImportByUnkParameter),
+    /// print an hexadecimal dump of a messagebin file, annotated with what each part of the file contains
+    HexDoc(HexDocParameter),
+    /// write the words used in the messages (without placeholders), with their number of occurrences, as CSV sorted from the most used
+    Glossary(GlossaryParameter),
+    /// print the hash, offset and length (in bytes) of the string of each message, in the order of the file, as CSV
+    OffsetMap(OffsetMapParameter),
+    /// decode a messagebin file into a JSON array of messages, in the order of the file (or sorted by hash with --sort-by-hash)
+    Export(ExportParameter),
+    /// encode a JSON array of messages (as written by export) into a messagebin file
+    Import(ImportParameter),
+    /// print the hash, unknown value and text of each message, one per line, separated by tabulations
+    List(ListParameter),
+    /// print statistics on the messages: their number, how many are empty, their number of characters, the placeholders they use and the range of their hashes
+    Stats(StatsParameter),
+    /// write the messages as a gettext PO template, with the hash as context, the text as msgid and an empty msgstr
+    PoExport(PoExportParameter),
+    /// decode a messagebin file into a CSV file with the hash, unk and text columns, in the order of the file
+    CsvExport(CsvExportParameter),
+    /// encode a CSV file (as written by csv-export) into a messagebin file
+    CsvImport(CsvImportParameter),
+    /// check that a messagebin file contains the messages of a JSON or CSV file (as written by export or csv-export), printing the hashes that differ.
+    /// Exit with the status 0 if they match, 1 if they differ, and 2 on error.
+    Check(CheckParameter),
+}
+
+/// A message, as stored in the JSON and CSV files of the export, import, csv-export and csv-import subcommands
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct MessageRecord {
+    #[serde(with = "hex_hash")]
+    hash: u32,
+    unk: u32,
+    text: String,
 }
 
-#[derive(Clap)]
+/// (De)serialize a hash as 0x followed by 8 uppercase hexadecimal digits, the format used for hashes by all the subcommands
+mod hex_hash {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(hash: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("0x{:08X}", hash))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.strip_prefix("0x")
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .ok_or_else(|| {
+                D::Error::custom(format!(
+                    "the hash {:?} should be 0x followed by hexadecimal digits",
+                    text
+                ))
+            })
+    }
+}
+
+#[derive(Args)]
 struct ReencodeParameter {
+    /// the input messagebin file to read
+    input: PathBuf,
+    /// path to the code_table.bin file, used to decode and encode unless overridden by --decode-table or --encode-table
+    code_table: PathBuf,
+    /// the output messagebin file to write
+    output: PathBuf,
+    /// path to the code_table.bin file used to decode the input (like the one of another region)
+    #[clap(long)]
+    decode_table: Option<PathBuf>,
+    /// path to the code_table.bin file used to encode the output (like the one of another region)
+    #[clap(long)]
+    encode_table: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct DiffParameter {
+    /// the original messagebin file
+    original: PathBuf,
+    /// the modified messagebin file
+    modified: PathBuf,
+    /// path to the code_table.bin file
+    code_table: PathBuf,
+}
+
+#[derive(Args)]
+struct ExportByUnkParameter {
     /// the input messagebin file to read
     input: PathBuf,
     /// path to the code_table.bin file
     code_table: PathBuf,
+    /// the folder to write the messagebin files to
+    output: PathBuf,
+}
+
+#[derive(Args)]
+struct ImportByUnkParameter {
+    /// the folder containing the .bin files to merge
+    input: PathBuf,
+    /// path to the code_table.bin file
+    code_table: PathBuf,
     /// the output messagebin file to write
     output: PathBuf,
 }
 
+#[derive(Args)]
+struct HexDocParameter {
+    /// the input messagebin file to read
+    input: PathBuf,
+    /// the number of bytes displayed per line
+    #[clap(long, default_value = "16")]
+    width: usize,
+}
+
+#[derive(Args)]
+struct GlossaryParameter {
+    /// the input messagebin file to read
+    input: PathBuf,
+    /// path to the code_table.bin file
+    code_table: PathBuf,
+    /// the output CSV file to write
+    output: PathBuf,
+    /// don't write the words used less than this number of times
+    #[clap(long, default_value = "1")]
+    min_count: usize,
+}
+
+#[derive(Args)]
+struct OffsetMapParameter {
+    /// the input messagebin file to read
+    input: PathBuf,
+    /// print JSON instead of CSV
+    #[clap(long)]
+    json: bool,
+}
+
+#[derive(Args)]
+struct ExportParameter {
+    /// the input messagebin file to read
+    input: PathBuf,
+    /// path to the code_table.bin file
+    code_table: PathBuf,
+    /// the output JSON file to write
+    output: PathBuf,
+    /// sort the messages by hash, so the JSON file is easier to compare between versions. The file written back by import will then have its strings in this order.
+    #[clap(long)]
+    sort_by_hash: bool,
+}
+
+#[derive(Args)]
+struct ImportParameter {
+    /// the input JSON file to read
+    input: PathBuf,
+    /// path to the code_table.bin file
+    code_table: PathBuf,
+    /// the output messagebin file to write
+    output: PathBuf,
+}
+
+#[derive(Args)]
+struct ListParameter {
+    /// the input messagebin file to read
+    input: PathBuf,
+    /// path to the code_table.bin file
+    #[clap(required_unless_present = "no-code-table")]
+    code_table: Option<PathBuf>,
+    /// don't decode the texts with a code table, interpreting them as UTF-16
+    #[clap(long)]
+    no_code_table: bool,
+}
+
+#[derive(Args)]
+struct StatsParameter {
+    /// the input messagebin file to read
+    input: PathBuf,
+    /// path to the code_table.bin file
+    code_table: PathBuf,
+}
+
+#[derive(Args)]
+struct PoExportParameter {
+    /// the input messagebin file to read
+    input: PathBuf,
+    /// path to the code_table.bin file
+    code_table: PathBuf,
+    /// the output PO file to write
+    output: PathBuf,
+}
+
+#[derive(Args)]
+struct CsvExportParameter {
+    /// the input messagebin file to read
+    input: PathBuf,
+    /// path to the code_table.bin file
+    code_table: PathBuf,
+    /// the output CSV file to write
+    output: PathBuf,
+}
+
+#[derive(Args)]
+struct CsvImportParameter {
+    /// the input CSV file to read
+    input: PathBuf,
+    /// path to the code_table.bin file
+    code_table: PathBuf,
+    /// the output messagebin file to write
+    output: PathBuf,
+}
+
+#[derive(Args)]
+struct CheckParameter {
+    /// the messagebin file to check
+    input: PathBuf,
+    /// the JSON or CSV file (chosen by the .csv extension) the messagebin file should match
+    source: PathBuf,
+    /// path to the code_table.bin file, used to encode the source
+    code_table: PathBuf,
+}
+
 fn main() -> Result<()> {
     let opts = Opts::parse();
 
     match opts.subcmd {
         SubCommand::Reencode(ep) => reencode(ep)?,
+        SubCommand::ExportByUnk(ep) => export_by_unk(ep)?,
+        SubCommand::ImportByUnk(ip) => import_by_unk(ip)?,
+        SubCommand::HexDoc(hp) => hex_doc(hp)?,
+        SubCommand::Glossary(gp) => glossary(gp)?,
+        SubCommand::OffsetMap(op) => offset_map(op)?,
+        SubCommand::Export(ep) => export(ep)?,
+        SubCommand::Import(ip) => import(ip)?,
+        SubCommand::List(lp) => list(lp)?,
+        SubCommand::Stats(sp) => stats(sp)?,
+        SubCommand::PoExport(pp) => po_export(pp)?,
+        SubCommand::CsvExport(cp) => csv_export(cp)?,
+        SubCommand::CsvImport(cp) => csv_import(cp)?,
+        SubCommand::Diff(dp) => exit_on_difference(diff(dp)),
+        SubCommand::Check(cp) => exit_on_difference(check(cp)),
     }
 
     Ok(())
 }
 
+/// Like diff(1), exit with the status 1 if the compared files differ (`Ok(false)`) and 2 on error
+fn exit_on_difference(result: Result<bool>) {
+    match result {
+        Ok(true) => (),
+        Ok(false) => process::exit(1),
+        // 1 is already used for differences
+        Err(err) => {
+            eprintln!("Error: {:?}", err);
+            process::exit(2);
+        }
+    }
+}
+
 fn reencode(rp: ReencodeParameter) -> Result<()> {
+    let decode_table_path = rp.decode_table.as_ref().unwrap_or(&rp.code_table);
+    let encode_table_path = rp.encode_table.as_ref().unwrap_or(&rp.code_table);
     println!("reading the code table");
-    let code_table_file = BufReader::new(File::open(&rp.code_table).context("can't open the code table file")?);
-    let mut code_table = CodeTable::new_from_file(code_table_file).context("can't read the code table file")?;
-    code_table.add_missing();
-    
-    let code_to_text = code_table.generate_code_to_text();
-    let text_to_code = code_table.generate_text_to_code();
+    let decode_table = load_code_table(decode_table_path).with_context(|| {
+        format!(
+            "can't load the decoding code table at {}",
+            decode_table_path.display()
+        )
+    })?;
+    let code_to_text = decode_table.generate_code_to_text();
+    let encode_table = if encode_table_path == decode_table_path {
+        None
+    } else {
+        println!("reading the encoding code table");
+        Some(load_code_table(encode_table_path).with_context(|| {
+            format!(
+                "can't load the encoding code table at {}",
+                encode_table_path.display()
+            )
+        })?)
+    };
+    let text_to_code = encode_table
+        .as_ref()
+        .unwrap_or(&decode_table)
+        .generate_text_to_code();
 
     println!("decoding...");
     let mut input_file =
         BufReader::new(File::open(&rp.input).context("can't open the input file")?);
-    let message =
-        MessageBin::load_file(&mut input_file, Some(&code_to_text)).context("can't extract the messagebin file")?;
+    let message = MessageBin::load_file(&mut input_file, Some(&code_to_text))
+        .context("can't extract the messagebin file")?;
 
     println!("encoding...");
     let mut output_file =
@@ -65,3 +325,1174 @@ fn reencode(rp: ReencodeParameter) -> Result<()> {
     println!("done !");
     Ok(())
 }
+
+fn export(ep: ExportParameter) -> Result<()> {
+    let mut records = load_records(&ep.input, &ep.code_table)?;
+    if ep.sort_by_hash {
+        records.sort_by_key(|record| record.hash);
+    }
+    let mut output_file =
+        BufWriter::new(File::create(&ep.output).context("can't open the result file")?);
+    serde_json::to_writer_pretty(&mut output_file, &records)
+        .context("can't write the JSON file")?;
+    output_file.flush()?;
+    println!("exported {} messages", records.len());
+    Ok(())
+}
+
+fn import(ip: ImportParameter) -> Result<()> {
+    let records = read_json_records(&ip.input)?;
+    write_records(records, &ip.code_table, &ip.output)
+}
+
+/// Read the records of a JSON file written by export
+fn read_json_records(input: &Path) -> Result<Vec<MessageRecord>> {
+    let input_file = BufReader::new(File::open(input).context("can't open the input file")?);
+    serde_json::from_reader(input_file).context("can't read the JSON file")
+}
+
+fn csv_export(cp: CsvExportParameter) -> Result<()> {
+    let records = load_records(&cp.input, &cp.code_table)?;
+    let mut writer = csv::Writer::from_path(&cp.output).context("can't open the result file")?;
+    for record in &records {
+        writer
+            .serialize(record)
+            .context("can't write the CSV file")?;
+    }
+    writer.flush()?;
+    println!("exported {} messages", records.len());
+    Ok(())
+}
+
+fn csv_import(cp: CsvImportParameter) -> Result<()> {
+    let records = read_csv_records(&cp.input)?;
+    write_records(records, &cp.code_table, &cp.output)
+}
+
+/// Read the records of a CSV file written by csv-export
+fn read_csv_records(input: &Path) -> Result<Vec<MessageRecord>> {
+    let mut reader = csv::Reader::from_path(input).context("can't open the input file")?;
+    reader
+        .deserialize()
+        .collect::<Result<Vec<MessageRecord>, _>>()
+        .context("can't read the CSV file")
+}
+
+/// Load and decode a messagebin file as a list of [`MessageRecord`], in the order of the file
+fn load_records(input: &Path, code_table: &Path) -> Result<Vec<MessageRecord>> {
+    let code_table = load_code_table(code_table)?;
+    let code_to_text = code_table.generate_code_to_text();
+
+    let mut input_file = BufReader::new(File::open(input).context("can't open the input file")?);
+    let message = MessageBin::load_file(&mut input_file, Some(&code_to_text))
+        .context("can't extract the messagebin file")?;
+    Ok(message_records(&message))
+}
+
+/// Return the messages as a list of [`MessageRecord`], in the order of the file
+fn message_records(message: &MessageBin) -> Vec<MessageRecord> {
+    message
+        .iter()
+        .map(|(hash, unk, text)| MessageRecord {
+            hash,
+            unk,
+            text: text.to_string(),
+        })
+        .collect()
+}
+
+/// Encode the records and write them as a messagebin file. A later record with the same hash replace the earlier one.
+fn write_records(records: Vec<MessageRecord>, code_table: &Path, output: &Path) -> Result<()> {
+    let code_table = load_code_table(code_table)?;
+    let text_to_code = code_table.generate_text_to_code();
+
+    let message = MessageBin::from_messages(
+        records
+            .into_iter()
+            .map(|record| (record.hash, record.unk, record.text)),
+    );
+
+    let mut output_file =
+        BufWriter::new(File::create(output).context("can't open the result file")?);
+    message
+        .write(&mut output_file, Some(&text_to_code))
+        .context("can't encode/write the messagebin file")?;
+    output_file.flush()?;
+    println!("imported {} messages", message.len());
+    Ok(())
+}
+
+/// Print the messages that differ between the messagebin file and its source, and return true if they match.
+fn check(cp: CheckParameter) -> Result<bool> {
+    write_check(cp, &mut io::stdout().lock())
+}
+
+/// Write the differences found by the check subcommand to `output`, and return true if there are none
+fn write_check<W: Write>(cp: CheckParameter, output: &mut W) -> Result<bool> {
+    let records = if cp.source.extension() == Some("csv".as_ref()) {
+        read_csv_records(&cp.source)?
+    } else {
+        read_json_records(&cp.source)?
+    };
+    let source = MessageBin::from_messages(
+        records
+            .into_iter()
+            .map(|record| (record.hash, record.unk, record.text)),
+    );
+
+    // compare the encoded strings, as stored in the file, rather than the texts
+    let code_table = load_code_table(&cp.code_table)?;
+    let text_to_code = code_table.generate_text_to_code();
+    let source_bytes = source
+        .to_bytes(Some(&text_to_code))
+        .context("can't encode the source")?;
+    let encoded_source = MessageBin::from_bytes(&source_bytes, None)
+        .context("can't read back the encoded source")?;
+    let mut input_file =
+        BufReader::new(File::open(&cp.input).context("can't open the input file")?);
+    let binary =
+        MessageBin::load_file(&mut input_file, None).context("can't read the messagebin file")?;
+
+    let difference = binary.diff(&encoded_source);
+    for hash in &difference.removed {
+        writeln!(output, "0x{:08X}: only in the messagebin file", hash)?;
+    }
+    for hash in &difference.added {
+        writeln!(output, "0x{:08X}: only in the source", hash)?;
+    }
+    for hash in &difference.changed {
+        writeln!(output, "0x{:08X}: differ", hash)?;
+    }
+    Ok(difference.is_empty())
+}
+
+fn list(lp: ListParameter) -> Result<()> {
+    write_list(lp, &mut io::stdout().lock())
+}
+
+/// Write the lines printed by the list subcommand to `output`
+fn write_list<W: Write>(lp: ListParameter, output: &mut W) -> Result<()> {
+    let code_table = match (&lp.code_table, lp.no_code_table) {
+        (Some(code_table_path), false) => Some(load_code_table(code_table_path)?),
+        _ => None,
+    };
+    let code_to_text = code_table
+        .as_ref()
+        .map(|code_table| code_table.generate_code_to_text());
+
+    let mut input_file =
+        BufReader::new(File::open(&lp.input).context("can't open the input file")?);
+    let message = MessageBin::load_file(
+        &mut input_file,
+        code_to_text
+            .as_ref()
+            .map(|code_to_text| code_to_text as &dyn Decoder),
+    )
+    .context("can't extract the messagebin file")?;
+
+    for (hash, unk, text) in &message {
+        writeln!(
+            output,
+            "0x{:08X}\t{}\t{}",
+            hash,
+            unk,
+            text.replace('\n', "\\n")
+        )?;
+    }
+    Ok(())
+}
+
+fn stats(sp: StatsParameter) -> Result<()> {
+    let code_table = load_code_table(&sp.code_table)?;
+    let code_to_text = code_table.generate_code_to_text();
+
+    let mut input_file =
+        BufReader::new(File::open(&sp.input).context("can't open the input file")?);
+    let message = MessageBin::load_file(&mut input_file, Some(&code_to_text))
+        .context("can't extract the messagebin file")?;
+
+    let stats = message.stats();
+    println!("messages: {}", stats.message_count);
+    println!("empty messages: {}", stats.empty_count);
+    println!("characters: {}", stats.char_count);
+    if let (Some(min_hash), Some(max_hash)) = (stats.min_hash, stats.max_hash) {
+        println!("hashes: 0x{:08X} to 0x{:08X}", min_hash, max_hash);
+    }
+    println!("messages using each placeholder:");
+    for (placeholder, count) in &stats.placeholder_messages {
+        println!("  {}: {}", placeholder, count);
+    }
+    Ok(())
+}
+
+fn po_export(pp: PoExportParameter) -> Result<()> {
+    let code_table = load_code_table(&pp.code_table)?;
+    let code_to_text = code_table.generate_code_to_text();
+
+    let mut input_file =
+        BufReader::new(File::open(&pp.input).context("can't open the input file")?);
+    let message = MessageBin::load_file(&mut input_file, Some(&code_to_text))
+        .context("can't extract the messagebin file")?;
+
+    let mut output_file =
+        BufWriter::new(File::create(&pp.output).context("can't open the result file")?);
+    writeln!(output_file, "msgid \"\"")?;
+    write_po_string(
+        &mut output_file,
+        "msgstr",
+        "MIME-Version: 1.0\nContent-Type: text/plain; charset=UTF-8\nContent-Transfer-Encoding: 8bit\n",
+    )?;
+    for (hash, unk, text) in &message {
+        write_po_entry(&mut output_file, hash, unk, text)?;
+    }
+    output_file.flush()?;
+    println!("exported {} messages", message.len());
+    Ok(())
+}
+
+/// Write the PO entry of a message, preceded by an empty line, with the hash as context and an empty translation
+fn write_po_entry<W: Write>(output: &mut W, hash: u32, unk: u32, text: &str) -> Result<()> {
+    writeln!(output)?;
+    writeln!(output, "#. unk: {}", unk)?;
+    writeln!(output, "msgctxt \"0x{:08X}\"", hash)?;
+    write_po_string(output, "msgid", text)?;
+    writeln!(output, "msgstr \"\"")?;
+    Ok(())
+}
+
+/// Write a PO keyword with its quoted and escaped value, with one line per line of the text when there are line breaks
+fn write_po_string<W: Write>(output: &mut W, keyword: &str, text: &str) -> Result<()> {
+    if !text.contains('\n') {
+        writeln!(output, "{} \"{}\"", keyword, escape_po(text))?;
+        return Ok(());
+    }
+    writeln!(output, "{} \"\"", keyword)?;
+    let mut lines = text.split('\n').peekable();
+    while let Some(line) = lines.next() {
+        if lines.peek().is_some() {
+            writeln!(output, "\"{}\\n\"", escape_po(line))?;
+        } else if !line.is_empty() {
+            writeln!(output, "\"{}\"", escape_po(line))?;
+        }
+    }
+    Ok(())
+}
+
+/// Escape a line of text for a PO string, as read by gettext. Line breaks are handled by [`write_po_string`].
+fn escape_po(line: &str) -> String {
+    let mut escaped = String::with_capacity(line.len());
+    for chara in line.chars() {
+        match chara {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            '\u{7}' => escaped.push_str("\\a"),
+            '\u{8}' => escaped.push_str("\\b"),
+            '\u{B}' => escaped.push_str("\\v"),
+            '\u{C}' => escaped.push_str("\\f"),
+            // other control characters can only be written as octal escapes
+            chara if chara.is_ascii_control() => {
+                escaped.push_str(&format!("\\{:03o}", chara as u32))
+            }
+            chara => escaped.push(chara),
+        }
+    }
+    escaped
+}
+
+fn load_code_table(path: &Path) -> Result<CodeTable> {
+    let code_table_file =
+        BufReader::new(File::open(path).context("can't open the code table file")?);
+    let mut code_table =
+        CodeTable::new_from_file(code_table_file).context("can't read the code table file")?;
+    code_table.add_missing();
+    Ok(code_table)
+}
+
+/// Print the differences between the two files, and return true if they are identical.
+fn diff(dp: DiffParameter) -> Result<bool> {
+    let code_table = load_code_table(&dp.code_table)?;
+    let code_to_text = code_table.generate_code_to_text();
+
+    let mut original_file =
+        BufReader::new(File::open(&dp.original).context("can't open the original file")?);
+    let original = MessageBin::load_file(&mut original_file, Some(&code_to_text))
+        .context("can't extract the original messagebin file")?;
+    let mut modified_file =
+        BufReader::new(File::open(&dp.modified).context("can't open the modified file")?);
+    let modified = MessageBin::load_file(&mut modified_file, Some(&code_to_text))
+        .context("can't extract the modified messagebin file")?;
+
+    let difference = original.diff(&modified);
+    if difference.is_empty() {
+        return Ok(true);
+    }
+
+    let index = |message: &MessageBin| -> BTreeMap<u32, (u32, String)> {
+        message
+            .messages()
+            .iter()
+            .map(|(hash, unk, text)| (*hash, (*unk, text.clone())))
+            .collect()
+    };
+    let original_index = index(&original);
+    let modified_index = index(&modified);
+
+    println!("--- {}", dp.original.display());
+    println!("+++ {}", dp.modified.display());
+    for hash in &difference.removed {
+        let (unk, text) = &original_index[hash];
+        println!("@@ 0x{:08X} removed @@", hash);
+        println!("-unk: {}", unk);
+        print_diff_lines('-', text);
+    }
+    for hash in &difference.changed {
+        let (original_unk, original_text) = &original_index[hash];
+        let (modified_unk, modified_text) = &modified_index[hash];
+        println!("@@ 0x{:08X} changed @@", hash);
+        if original_unk != modified_unk {
+            println!("-unk: {}", original_unk);
+            println!("+unk: {}", modified_unk);
+        }
+        if original_text != modified_text {
+            print_diff_lines('-', original_text);
+            print_diff_lines('+', modified_text);
+        }
+    }
+    for hash in &difference.added {
+        let (unk, text) = &modified_index[hash];
+        println!("@@ 0x{:08X} added @@", hash);
+        println!("+unk: {}", unk);
+        print_diff_lines('+', text);
+    }
+
+    Ok(false)
+}
+
+fn print_diff_lines(prefix: char, text: &str) {
+    for line in text.split('\n') {
+        println!("{}{}", prefix, line);
+    }
+}
+
+fn export_by_unk(ep: ExportByUnkParameter) -> Result<()> {
+    let code_table = load_code_table(&ep.code_table)?;
+    let code_to_text = code_table.generate_code_to_text();
+    let text_to_code = code_table.generate_text_to_code();
+
+    let mut input_file =
+        BufReader::new(File::open(&ep.input).context("can't open the input file")?);
+    let message = MessageBin::load_file(&mut input_file, Some(&code_to_text))
+        .context("can't extract the messagebin file")?;
+
+    fs::create_dir_all(&ep.output).context("can't create the output folder")?;
+    let order_path = ep.output.join(ORDER_FILE_NAME);
+    let mut order_file = BufWriter::new(
+        File::create(&order_path)
+            .with_context(|| format!("can't create the file {:?}", order_path))?,
+    );
+    for hash in message.keys() {
+        writeln!(order_file, "0x{:08X}", hash)?;
+    }
+    order_file.flush()?;
+
+    for (unk, group) in message.group_by_unk() {
+        let output_path = ep.output.join(format!("{}.bin", unk));
+        let mut output_file = BufWriter::new(
+            File::create(&output_path)
+                .with_context(|| format!("can't create the file {:?}", output_path))?,
+        );
+        group
+            .write(&mut output_file, Some(&text_to_code))
+            .with_context(|| format!("can't encode/write the file {:?}", output_path))?;
+        println!(
+            "wrote {} messages to {:?}",
+            group.messages().len(),
+            output_path
+        );
+    }
+    Ok(())
+}
+
+/// The file listing the hashes of the messages in their original order, written by export-by-unk
+const ORDER_FILE_NAME: &str = "order.txt";
+
+fn import_by_unk(ip: ImportByUnkParameter) -> Result<()> {
+    let code_table = load_code_table(&ip.code_table)?;
+    let code_to_text = code_table.generate_code_to_text();
+    let text_to_code = code_table.generate_text_to_code();
+
+    let mut input_paths = Vec::new();
+    for entry in fs::read_dir(&ip.input).context("can't list the input folder")? {
+        let path = entry.context("can't list the input folder")?.path();
+        if path.extension() == Some("bin".as_ref()) {
+            input_paths.push(path);
+        }
+    }
+    // by numeric unknown value (so 2.bin come before 10.bin), then files not named after one
+    input_paths.sort_by_cached_key(|path| {
+        let unk = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse::<u32>().ok());
+        (unk.is_none(), unk, path.clone())
+    });
+
+    let mut groups = Vec::new();
+    for input_path in input_paths {
+        let mut input_file = BufReader::new(
+            File::open(&input_path)
+                .with_context(|| format!("can't open the file {:?}", input_path))?,
+        );
+        groups.push(
+            MessageBin::load_file(&mut input_file, Some(&code_to_text))
+                .with_context(|| format!("can't extract the file {:?}", input_path))?,
+        );
+    }
+
+    let mut message = MessageBin::default();
+    let order_path = ip.input.join(ORDER_FILE_NAME);
+    if order_path.exists() {
+        let order = fs::read_to_string(&order_path)
+            .with_context(|| format!("can't read the file {:?}", order_path))?;
+        for line in order.lines().filter(|line| !line.is_empty()) {
+            let hash = line
+                .strip_prefix("0x")
+                .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+                .with_context(|| format!("invalid hash {:?} in {:?}", line, order_path))?;
+            if let Some(isolated) = groups.iter().find_map(|group| group.isolate(hash)) {
+                message.merge(&isolated);
+            }
+        }
+    }
+    // the messages already present keep their position, and those missing from the order file are added at the end
+    for group in &groups {
+        message.merge(group);
+    }
+
+    let mut output_file =
+        BufWriter::new(File::create(&ip.output).context("can't open the result file")?);
+    message
+        .write(&mut output_file, Some(&text_to_code))
+        .context("can't encode/write the messagebin file")?;
+    Ok(())
+}
+
+fn glossary(gp: GlossaryParameter) -> Result<()> {
+    let code_table = load_code_table(&gp.code_table)?;
+    let code_to_text = code_table.generate_code_to_text();
+
+    let mut input_file =
+        BufReader::new(File::open(&gp.input).context("can't open the input file")?);
+    let message = MessageBin::load_file(&mut input_file, Some(&code_to_text))
+        .context("can't extract the messagebin file")?;
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for (_, _, text) in message.messages() {
+        for segment in segment_for_translation(text) {
+            if let Segment::Translatable(translatable) = segment {
+                for word in translatable
+                    .split(|chara: char| !chara.is_alphanumeric())
+                    .filter(|word| !word.is_empty())
+                {
+                    *counts.entry(word.to_lowercase()).or_default() += 1;
+                }
+            }
+        }
+    }
+
+    let mut terms: Vec<(String, usize)> = counts
+        .into_iter()
+        .filter(|(_, count)| *count >= gp.min_count)
+        .collect();
+    terms.sort_by(|(word_a, count_a), (word_b, count_b)| {
+        count_b.cmp(count_a).then_with(|| word_a.cmp(word_b))
+    });
+
+    let mut output_file =
+        BufWriter::new(File::create(&gp.output).context("can't open the result file")?);
+    writeln!(output_file, "term,count")?;
+    for (word, count) in &terms {
+        writeln!(output_file, "{},{}", word, count)?;
+    }
+    output_file.flush()?;
+    println!("wrote {} terms", terms.len());
+    Ok(())
+}
+
+fn offset_map(op: OffsetMapParameter) -> Result<()> {
+    write_offset_map(op, &mut io::stdout().lock())
+}
+
+/// The position of the string of a message, as printed by the offset-map subcommand
+#[derive(Serialize)]
+struct OffsetRecord {
+    #[serde(with = "hex_hash")]
+    hash: u32,
+    offset: u64,
+    length: u64,
+}
+
+/// Write the output of the offset-map subcommand to `output`
+fn write_offset_map<W: Write>(op: OffsetMapParameter, output: &mut W) -> Result<()> {
+    let mut input_file =
+        BufReader::new(File::open(&op.input).context("can't open the input file")?);
+    let offsets = MessageBin::string_offsets(&mut input_file, &MessageBinReadOptions::default())
+        .context("can't read the metadata of the messagebin file")?;
+    let records = offsets.iter().map(|string_offset| OffsetRecord {
+        hash: string_offset.hash,
+        offset: string_offset.offset,
+        length: string_offset.length,
+    });
+
+    if op.json {
+        serde_json::to_writer_pretty(&mut *output, &records.collect::<Vec<_>>())
+            .context("can't write the JSON output")?;
+        writeln!(output)?;
+    } else {
+        let mut writer = csv::Writer::from_writer(output);
+        for record in records {
+            writer
+                .serialize(record)
+                .context("can't write the CSV output")?;
+        }
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+fn hex_doc(hp: HexDocParameter) -> Result<()> {
+    if hp.width == 0 {
+        anyhow::bail!("the width should be at least 1");
+    }
+    let content = fs::read(&hp.input).context("can't read the input file")?;
+    let regions = MessageBin::layout_regions(
+        &mut Cursor::new(&content),
+        &MessageBinReadOptions::default(),
+    )
+    .context("can't read the layout of the messagebin file")?;
+
+    for region in regions {
+        let start = region.start as usize;
+        let end = (start + region.length as usize).min(content.len());
+        let bytes = content.get(start..end).unwrap_or(&[]);
+        match region.kind {
+            LayoutRegionKind::Sir0Header => {
+                println!("# sir0 header");
+                print_hex_field(&content, start, "magic", None);
+                print_hex_field(
+                    &content,
+                    start + 4,
+                    "pointer to the header",
+                    Some(read_u32(&content, start + 4)),
+                );
+                print_hex_field(
+                    &content,
+                    start + 8,
+                    "pointer to the footer",
+                    Some(read_u32(&content, start + 8)),
+                );
+                print_hex_lines(&bytes[12.min(bytes.len())..], start + 12, hp.width);
+            }
+            LayoutRegionKind::String { hashes } => {
+                let hashes = hashes
+                    .iter()
+                    .map(|hash| format!("0x{:08X}", hash))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                println!("# string of {} ({} bytes)", hashes, bytes.len());
+                print_hex_lines(bytes, start, hp.width);
+            }
+            LayoutRegionKind::StringMetadata { hash } => {
+                println!("# metadata entry of 0x{:08X}", hash);
+                print_hex_field(
+                    &content,
+                    start,
+                    "pointer to the string",
+                    Some(read_u32(&content, start)),
+                );
+                print_hex_field(
+                    &content,
+                    start + 4,
+                    "hash",
+                    Some(read_u32(&content, start + 4)),
+                );
+                print_hex_field(
+                    &content,
+                    start + 8,
+                    "unk",
+                    Some(read_u32(&content, start + 8)),
+                );
+            }
+            LayoutRegionKind::Header => {
+                println!("# header");
+                print_hex_field(
+                    &content,
+                    start,
+                    "number of strings",
+                    Some(read_u32(&content, start)),
+                );
+                print_hex_field(
+                    &content,
+                    start + 4,
+                    "pointer to the metadata",
+                    Some(read_u32(&content, start + 4)),
+                );
+            }
+            LayoutRegionKind::Sir0Footer => {
+                println!("# sir0 footer ({} bytes)", bytes.len());
+                print_hex_lines(bytes, start, hp.width);
+            }
+            LayoutRegionKind::Padding => {
+                println!("# padding ({} bytes)", bytes.len());
+                print_hex_lines(bytes, start, hp.width);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_u32(content: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0; 4];
+    if let Some(source) = content.get(offset..offset + 4) {
+        bytes.copy_from_slice(source);
+    }
+    u32::from_le_bytes(bytes)
+}
+
+fn format_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn print_hex_lines(bytes: &[u8], start: usize, width: usize) {
+    for (index, line) in bytes.chunks(width).enumerate() {
+        println!("{:08X}: {}", start + index * width, format_hex(line));
+    }
+}
+
+fn print_hex_field(content: &[u8], offset: usize, name: &str, value: Option<u32>) {
+    let bytes = content
+        .get(offset..(offset + 4).min(content.len()))
+        .unwrap_or(&[]);
+    match value {
+        Some(value) => println!(
+            "{:08X}: {:<11}  # {} = 0x{:08X}",
+            offset,
+            format_hex(bytes),
+            name,
+            value
+        ),
+        None => println!("{:08X}: {:<11}  # {}", offset, format_hex(bytes), name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pmd_message::MessageBinWriteError;
+
+    /// Create an empty directory for a test, in the temporary directory
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("messagetool-test-{}-{}", process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Write a code_table.bin file with the given placeholder names and code points
+    fn write_code_table(path: &Path, entries: &[(&str, u16)]) {
+        let mut bytes = vec![0; 16];
+        let mut string_positions = Vec::new();
+        for (name, _) in entries {
+            string_positions.push(bytes.len() as u32);
+            for unit in name.encode_utf16().chain(std::iter::once(0)) {
+                bytes.extend_from_slice(&unit.to_le_bytes());
+            }
+            bytes.resize(bytes.len().div_ceil(4) * 4, 0);
+        }
+        // the code table read its entries from the fourth to the third to last pointer
+        let mut sir0_offsets = vec![4, 8, 12];
+        for ((_, value), string_position) in entries.iter().zip(string_positions) {
+            sir0_offsets.push(bytes.len() as u32);
+            bytes.extend_from_slice(&string_position.to_le_bytes());
+            bytes.extend_from_slice(&value.to_le_bytes());
+            bytes.extend_from_slice(&[0; 6]);
+        }
+        let header_position = bytes.len() as u32;
+        sir0_offsets.push(header_position);
+        sir0_offsets.push(header_position + 4);
+        bytes.extend_from_slice(&[0; 16]);
+        let footer_position = bytes.len() as u32;
+        pmd_sir0::write_sir0_footer(&mut bytes, &sir0_offsets).unwrap();
+        // the last byte of the file isn't read as part of the pointer list
+        bytes.push(0);
+        let mut file = Cursor::new(bytes);
+        pmd_sir0::write_sir0_header(&mut file, header_position, footer_position).unwrap();
+        fs::write(path, file.into_inner()).unwrap();
+    }
+
+    /// Write a code table where `[K]` is the code point 0xE000, and a messagebin file using it, in `dir`.
+    /// Return the path of the messagebin file and of the code table.
+    fn write_fixture(dir: &Path) -> (PathBuf, PathBuf) {
+        let code_table = dir.join("code_table.bin");
+        write_code_table(&code_table, &[("K", 0xE000)]);
+        let message = MessageBin::from_messages(vec![
+            (0x30, 1, "Hello!\u{E000}How are you?".to_string()),
+            (0x10, 2, String::new()),
+            (0x20, 1, "a, \"quoted\" text\non two lines".to_string()),
+        ]);
+        let input = dir.join("message.bin");
+        fs::write(&input, message.to_bytes(None).unwrap()).unwrap();
+        (input, code_table)
+    }
+
+    #[test]
+    fn export_then_import() {
+        let dir = test_dir("export_then_import");
+        let (input, code_table) = write_fixture(&dir);
+        export(ExportParameter {
+            input: input.clone(),
+            code_table: code_table.clone(),
+            output: dir.join("message.json"),
+            sort_by_hash: false,
+        })
+        .unwrap();
+        let json = fs::read_to_string(dir.join("message.json")).unwrap();
+        assert!(json.contains("Hello![K]How are you?"));
+        assert!(json.contains(r#""hash": "0x00000030""#));
+
+        import(ImportParameter {
+            input: dir.join("message.json"),
+            code_table,
+            output: dir.join("imported.bin"),
+        })
+        .unwrap();
+        assert_eq!(
+            fs::read(dir.join("imported.bin")).unwrap(),
+            fs::read(input).unwrap()
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn import_report_hash_of_unencodable_message() {
+        let dir = test_dir("import_report_hash");
+        let (_, code_table) = write_fixture(&dir);
+        fs::write(
+            dir.join("message.json"),
+            r#"[{"hash": "0x00000001", "unk": 0, "text": "fine"}, {"hash": "0x00001234", "unk": 0, "text": "[UNKNOWN]"}]"#,
+        )
+        .unwrap();
+        let err = import(ImportParameter {
+            input: dir.join("message.json"),
+            code_table,
+            output: dir.join("imported.bin"),
+        })
+        .unwrap_err();
+        let write_error = err
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<MessageBinWriteError>())
+            .unwrap();
+        assert!(matches!(
+            write_error,
+            MessageBinWriteError::CantEncodeText { hash: 0x1234, .. }
+        ));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn csv_export_then_import() {
+        let dir = test_dir("csv_export_then_import");
+        let (input, code_table) = write_fixture(&dir);
+        csv_export(CsvExportParameter {
+            input: input.clone(),
+            code_table: code_table.clone(),
+            output: dir.join("message.csv"),
+        })
+        .unwrap();
+        let csv_content = fs::read_to_string(dir.join("message.csv")).unwrap();
+        assert!(csv_content.starts_with("hash,unk,text\n0x00000030,1,"));
+        assert!(csv_content.contains("\"a, \"\"quoted\"\" text\non two lines\""));
+        let records: Vec<MessageRecord> = csv::Reader::from_path(dir.join("message.csv"))
+            .unwrap()
+            .deserialize()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(records[2].text, "a, \"quoted\" text\non two lines");
+
+        csv_import(CsvImportParameter {
+            input: dir.join("message.csv"),
+            code_table,
+            output: dir.join("imported.bin"),
+        })
+        .unwrap();
+        assert_eq!(
+            fs::read(dir.join("imported.bin")).unwrap(),
+            fs::read(input).unwrap()
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn csv_import_reject_malformed_hash() {
+        let dir = test_dir("csv_import_malformed_hash");
+        let (_, code_table) = write_fixture(&dir);
+        fs::write(
+            dir.join("message.csv"),
+            "hash,unk,text\n0x0000000C,0,fine\n0x1G,0,broken\n",
+        )
+        .unwrap();
+        assert!(csv_import(CsvImportParameter {
+            input: dir.join("message.csv"),
+            code_table,
+            output: dir.join("imported.bin"),
+        })
+        .is_err());
+        assert!(!dir.join("imported.bin").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn export_records() {
+        let dir = test_dir("export_records");
+        let (input, code_table) = write_fixture(&dir);
+        let expected = vec![
+            MessageRecord {
+                hash: 0x30,
+                unk: 1,
+                text: "Hello![K]How are you?".to_string(),
+            },
+            MessageRecord {
+                hash: 0x10,
+                unk: 2,
+                text: String::new(),
+            },
+            MessageRecord {
+                hash: 0x20,
+                unk: 1,
+                text: "a, \"quoted\" text\non two lines".to_string(),
+            },
+        ];
+        assert_eq!(load_records(&input, &code_table).unwrap(), expected);
+
+        export(ExportParameter {
+            input: input.clone(),
+            code_table: code_table.clone(),
+            output: dir.join("message.json"),
+            sort_by_hash: false,
+        })
+        .unwrap();
+        let exported: Vec<MessageRecord> =
+            serde_json::from_str(&fs::read_to_string(dir.join("message.json")).unwrap()).unwrap();
+        assert_eq!(exported, expected);
+
+        export(ExportParameter {
+            input,
+            code_table,
+            output: dir.join("sorted.json"),
+            sort_by_hash: true,
+        })
+        .unwrap();
+        let sorted: Vec<MessageRecord> =
+            serde_json::from_str(&fs::read_to_string(dir.join("sorted.json")).unwrap()).unwrap();
+        assert_eq!(
+            sorted.iter().map(|record| record.hash).collect::<Vec<_>>(),
+            vec![0x10, 0x20, 0x30]
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn check_against_source() {
+        let dir = test_dir("check_against_source");
+        let (input, code_table) = write_fixture(&dir);
+        export(ExportParameter {
+            input: input.clone(),
+            code_table: code_table.clone(),
+            output: dir.join("message.json"),
+            sort_by_hash: true,
+        })
+        .unwrap();
+        csv_export(CsvExportParameter {
+            input: input.clone(),
+            code_table: code_table.clone(),
+            output: dir.join("message.csv"),
+        })
+        .unwrap();
+
+        let run_check = |source: &str| {
+            let mut output = Vec::new();
+            let matching = write_check(
+                CheckParameter {
+                    input: input.clone(),
+                    source: dir.join(source),
+                    code_table: code_table.clone(),
+                },
+                &mut output,
+            )
+            .unwrap();
+            (matching, String::from_utf8(output).unwrap())
+        };
+        // the order of the messages doesn't matter
+        assert_eq!(run_check("message.json"), (true, String::new()));
+        assert_eq!(run_check("message.csv"), (true, String::new()));
+
+        fs::write(
+            dir.join("edited.json"),
+            r#"[
+                {"hash": "0x00000030", "unk": 1, "text": "Hello![K]How are you?"},
+                {"hash": "0x00000010", "unk": 3, "text": ""},
+                {"hash": "0x00000040", "unk": 1, "text": "new"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            run_check("edited.json"),
+            (
+                false,
+                "0x00000020: only in the messagebin file\n0x00000040: only in the source\n0x00000010: differ\n"
+                    .to_string()
+            )
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn export_then_import_by_unk_keep_order() {
+        let dir = test_dir("export_then_import_by_unk");
+        let (_, code_table) = write_fixture(&dir);
+        let message = MessageBin::from_messages(vec![
+            (0x30, 10, "first".to_string()),
+            (0x10, 2, "second".to_string()),
+            (0x20, 10, "third".to_string()),
+            (0x40, 2, "fourth".to_string()),
+        ]);
+        let input = dir.join("message.bin");
+        fs::write(&input, message.to_bytes(None).unwrap()).unwrap();
+
+        export_by_unk(ExportByUnkParameter {
+            input: input.clone(),
+            code_table: code_table.clone(),
+            output: dir.join("groups"),
+        })
+        .unwrap();
+        import_by_unk(ImportByUnkParameter {
+            input: dir.join("groups"),
+            code_table: code_table.clone(),
+            output: dir.join("imported.bin"),
+        })
+        .unwrap();
+        let imported =
+            MessageBin::from_bytes(&fs::read(dir.join("imported.bin")).unwrap(), None).unwrap();
+        assert_eq!(imported.messages(), message.messages());
+        assert_eq!(
+            fs::read(dir.join("imported.bin")).unwrap(),
+            fs::read(&input).unwrap()
+        );
+
+        // without the order, the groups are merged by numeric unknown value
+        fs::remove_file(dir.join("groups").join(ORDER_FILE_NAME)).unwrap();
+        import_by_unk(ImportByUnkParameter {
+            input: dir.join("groups"),
+            code_table,
+            output: dir.join("unordered.bin"),
+        })
+        .unwrap();
+        let unordered =
+            MessageBin::from_bytes(&fs::read(dir.join("unordered.bin")).unwrap(), None).unwrap();
+        assert_eq!(
+            unordered.keys().collect::<Vec<_>>(),
+            vec![0x10, 0x40, 0x30, 0x20]
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn message_records_keep_file_order() {
+        let message =
+            MessageBin::from_messages(vec![(2, 0, "b".to_string()), (1, 5, "a".to_string())]);
+        let records = message_records(&message);
+        assert_eq!(
+            records.iter().map(|record| record.hash).collect::<Vec<_>>(),
+            vec![2, 1]
+        );
+        assert_eq!(records[1].unk, 5);
+    }
+
+    #[test]
+    fn list_one_line_per_message() {
+        let dir = test_dir("list_one_line_per_message");
+        let (input, code_table) = write_fixture(&dir);
+
+        let mut output = Vec::new();
+        write_list(
+            ListParameter {
+                input: input.clone(),
+                code_table: Some(code_table),
+                no_code_table: false,
+            },
+            &mut output,
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().count(), 3);
+        assert_eq!(
+            output.lines().next().unwrap(),
+            "0x00000030\t1\tHello![K]How are you?"
+        );
+
+        let mut output = Vec::new();
+        write_list(
+            ListParameter {
+                input,
+                code_table: None,
+                no_code_table: true,
+            },
+            &mut output,
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().count(), 3);
+        assert_eq!(
+            output.lines().next().unwrap(),
+            "0x00000030\t1\tHello!\u{E000}How are you?"
+        );
+        assert_eq!(
+            output.lines().nth(2).unwrap(),
+            "0x00000020\t1\ta, \"quoted\" text\\non two lines"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    fn po_string(text: &str) -> String {
+        let mut output = Vec::new();
+        write_po_string(&mut output, "msgid", text).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn po_string_escape() {
+        assert_eq!(po_string(""), "msgid \"\"\n");
+        assert_eq!(
+            po_string(r#"say "hi" \o/"#),
+            "msgid \"say \\\"hi\\\" \\\\o/\"\n"
+        );
+    }
+
+    #[test]
+    fn po_string_escape_control_characters() {
+        assert_eq!(po_string("a\tb\r\u{1}"), "msgid \"a\\tb\\r\\001\"\n");
+        assert_eq!(
+            po_string("name:\tvalue\nnext"),
+            "msgid \"\"\n\"name:\\tvalue\\n\"\n\"next\"\n"
+        );
+    }
+
+    #[test]
+    fn po_string_multi_line() {
+        assert_eq!(
+            po_string("first\nsecond \"line\""),
+            "msgid \"\"\n\"first\\n\"\n\"second \\\"line\\\"\"\n"
+        );
+        // a final line break doesn't produce an empty last line
+        assert_eq!(po_string("only\n"), "msgid \"\"\n\"only\\n\"\n");
+    }
+
+    #[test]
+    fn po_entry_layout() {
+        let mut output = Vec::new();
+        write_po_entry(&mut output, 0x1234, 7, "Hello\nWorld").unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "\n#. unk: 7\nmsgctxt \"0x00001234\"\nmsgid \"\"\n\"Hello\\n\"\n\"World\"\nmsgstr \"\"\n"
+        );
+    }
+
+    #[test]
+    fn reencode_with_different_tables() {
+        let dir = test_dir("reencode_with_different_tables");
+        let (input, code_table) = write_fixture(&dir);
+        let other_table = dir.join("other_code_table.bin");
+        write_code_table(&other_table, &[("K", 0xE001)]);
+
+        // the same table in both directions doesn't change the file
+        reencode(ReencodeParameter {
+            input: input.clone(),
+            code_table: code_table.clone(),
+            output: dir.join("same.bin"),
+            decode_table: None,
+            encode_table: None,
+        })
+        .unwrap();
+        assert_eq!(
+            fs::read(dir.join("same.bin")).unwrap(),
+            fs::read(&input).unwrap()
+        );
+
+        reencode(ReencodeParameter {
+            input: input.clone(),
+            code_table: code_table.clone(),
+            output: dir.join("remapped.bin"),
+            decode_table: None,
+            encode_table: Some(other_table.clone()),
+        })
+        .unwrap();
+        let remapped =
+            MessageBin::from_bytes(&fs::read(dir.join("remapped.bin")).unwrap(), None).unwrap();
+        assert_eq!(
+            remapped.message_by_hash(0x30).unwrap(),
+            "Hello!\u{E001}How are you?"
+        );
+
+        // and back, decoding with the other table
+        reencode(ReencodeParameter {
+            input: dir.join("remapped.bin"),
+            code_table,
+            output: dir.join("back.bin"),
+            decode_table: Some(other_table),
+            encode_table: None,
+        })
+        .unwrap();
+        assert_eq!(
+            fs::read(dir.join("back.bin")).unwrap(),
+            fs::read(&input).unwrap()
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn offset_map_same_hash_format() {
+        let dir = test_dir("offset_map_same_hash_format");
+        let (input, _) = write_fixture(&dir);
+
+        let mut csv_output = Vec::new();
+        write_offset_map(
+            OffsetMapParameter {
+                input: input.clone(),
+                json: false,
+            },
+            &mut csv_output,
+        )
+        .unwrap();
+        let csv_output = String::from_utf8(csv_output).unwrap();
+        let mut lines = csv_output.lines();
+        assert_eq!(lines.next().unwrap(), "hash,offset,length");
+        assert_eq!(lines.next().unwrap(), "0x00000030,16,40");
+
+        let mut json_output = Vec::new();
+        write_offset_map(OffsetMapParameter { input, json: true }, &mut json_output).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json_output).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 3);
+        assert_eq!(
+            json[0],
+            serde_json::json!({"hash": "0x00000030", "offset": 16, "length": 40})
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,68 @@
+use pmd_code_table::{CodeToText, TextToCode};
+use std::error::Error;
+
+/// The error returned by a [`Decoder`] or an [`Encoder`]. For the implementations of this crate, it can be downcasted to
+/// [`CodeToTextError`](pmd_code_table::CodeToTextError) or [`TextToCodeError`](pmd_code_table::TextToCodeError).
+pub type CodingError = Box<dyn Error + Send + Sync>;
+
+/// Convert the UTF-16 code units stored in a file into text.
+///
+/// Implemented by [`CodeToText`], but can be implemented for games using a different encoding.
+pub trait Decoder {
+    /// Decode the code units of a message (without the null terminator)
+    fn decode(&self, units: &[u16]) -> Result<String, CodingError>;
+}
+
+/// Convert a text into the UTF-16 code units stored in a file. This is the reverse of [`Decoder`].
+///
+/// Implemented by [`TextToCode`], but can be implemented for games using a different encoding.
+pub trait Encoder {
+    /// Encode the text of a message (without adding the null terminator)
+    fn encode(&self, text: &str) -> Result<Vec<u16>, CodingError>;
+}
+
+impl Decoder for CodeToText<'_> {
+    fn decode(&self, units: &[u16]) -> Result<String, CodingError> {
+        Ok(CodeToText::decode(self, units)?)
+    }
+}
+
+impl Encoder for TextToCode<'_> {
+    fn encode(&self, text: &str) -> Result<Vec<u16>, CodingError> {
+        Ok(TextToCode::encode(self, text)?)
+    }
+}
+
+/// What the texts of a [`MessageBin`](crate::MessageBin) contain, as recorded by the `load_file` functions.
+///
+/// [`MessageBin::write`](crate::MessageBin::write) refuse to write texts with an encoder if they weren't decoded, or without
+/// one if they were, as this would produce garbage.
+///
+/// ```
+/// use pmd_message::{CodingError, Encoder, MessageBin, MessageBinWriteError, TextMode};
+/// use std::io::Cursor;
+///
+/// struct Utf16;
+///
+/// impl Encoder for Utf16 {
+///     fn encode(&self, text: &str) -> Result<Vec<u16>, CodingError> {
+///         Ok(text.encode_utf16().collect())
+///     }
+/// }
+///
+/// let bytes = MessageBin::from_messages(vec![(1, 0, "Hello".to_string())]).to_bytes(None).unwrap();
+/// let raw = MessageBin::from_bytes(&bytes, None).unwrap();
+/// assert_eq!(raw.text_mode(), Some(TextMode::Raw));
+/// assert!(matches!(
+///     raw.write(&mut Cursor::new(Vec::new()), Some(&Utf16)),
+///     Err(MessageBinWriteError::TextModeMismatch { expected: TextMode::Raw, .. })
+/// ));
+/// raw.write(&mut Cursor::new(Vec::new()), None).unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextMode {
+    /// The texts were decoded with a [`Decoder`], and should be written with an [`Encoder`]
+    Decoded,
+    /// The texts are the code units of the file interpreted as UTF-16, and should be written without an [`Encoder`]
+    Raw,
+}
@@ -0,0 +1,181 @@
+use std::{collections::HashSet, ops::Range};
+
+/// The messages a [`ContextRule`] apply to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContextScope {
+    /// The messages whose hash is in this range
+    HashRange(Range<u32>),
+    /// The messages with this unknown value
+    Unk(u32),
+}
+
+impl ContextScope {
+    /// Return true if a message with this hash and unknown value is in the scope
+    pub fn contains(&self, hash: u32, unk: u32) -> bool {
+        match self {
+            Self::HashRange(range) => range.contains(&hash),
+            Self::Unk(scope_unk) => *scope_unk == unk,
+        }
+    }
+}
+
+/// Restrict the placeholders (like `[CS:A]`) usable in some messages. Placeholders are identified by their name, the part before the `:` (`CS` here).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextRule {
+    /// The messages this rule apply to
+    pub scope: ContextScope,
+    /// If set, only those placeholders are allowed
+    pub allowed: Option<HashSet<String>>,
+    /// Those placeholders are not allowed
+    pub forbidden: HashSet<String>,
+}
+
+/// A set of [`ContextRule`], used by [`MessageBin::lint_context`](crate::MessageBin::lint_context). A message should respect all the rules whose scope contain it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContextRules {
+    pub rules: Vec<ContextRule>,
+}
+
+/// A placeholder used in a message where a [`ContextRule`] doesn't permit it. `rule` is the index of this rule in [`ContextRules::rules`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContextViolation {
+    /// The placeholder is in the forbidden list of the rule
+    Forbidden { placeholder: String, rule: usize },
+    /// The rule has an allowed list, and it doesn't contain the placeholder
+    NotAllowed { placeholder: String, rule: usize },
+}
+
+impl ContextRules {
+    /// Return the violations of a message using the given placeholder names, in the order of the placeholders then of the rules.
+    pub(crate) fn check(
+        &self,
+        hash: u32,
+        unk: u32,
+        placeholders: &[&str],
+    ) -> Vec<ContextViolation> {
+        let mut violations = Vec::new();
+        for placeholder in placeholders {
+            for (rule_id, rule) in self.rules.iter().enumerate() {
+                if !rule.scope.contains(hash, unk) {
+                    continue;
+                };
+                if rule.forbidden.contains(*placeholder) {
+                    violations.push(ContextViolation::Forbidden {
+                        placeholder: placeholder.to_string(),
+                        rule: rule_id,
+                    });
+                } else if let Some(allowed) = &rule.allowed {
+                    if !allowed.contains(*placeholder) {
+                        violations.push(ContextViolation::NotAllowed {
+                            placeholder: placeholder.to_string(),
+                            rule: rule_id,
+                        });
+                    }
+                }
+            }
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MessageBin;
+
+    fn names(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn hash_range_scope() {
+        let message = MessageBin::from_messages(vec![
+            (0x10, 0, "[CS:A]Hello[CR]".to_string()),
+            (0x20, 0, "[CS:A]Menu".to_string()),
+            (0x30, 0, "[CS:A]Outside".to_string()),
+        ]);
+        let rules = ContextRules {
+            rules: vec![ContextRule {
+                scope: ContextScope::HashRange(0x10..0x30),
+                allowed: None,
+                forbidden: names(&["CS"]),
+            }],
+        };
+        assert_eq!(
+            message.lint_context(&rules),
+            vec![
+                (
+                    0x10,
+                    ContextViolation::Forbidden {
+                        placeholder: "CS".to_string(),
+                        rule: 0
+                    }
+                ),
+                (
+                    0x20,
+                    ContextViolation::Forbidden {
+                        placeholder: "CS".to_string(),
+                        rule: 0
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn unk_scope_forbidden_and_not_allowed() {
+        let message = MessageBin::from_messages(vec![
+            (0x10, 1, "[CS:A]Hi[K]".to_string()),
+            (0x20, 2, "[CS:A]Hi[K]".to_string()),
+            (0x30, 1, "[CR]".to_string()),
+        ]);
+        let rules = ContextRules {
+            rules: vec![
+                ContextRule {
+                    scope: ContextScope::Unk(1),
+                    allowed: Some(names(&["CR", "CS"])),
+                    forbidden: names(&["CS"]),
+                },
+                ContextRule {
+                    scope: ContextScope::Unk(1),
+                    allowed: Some(names(&["CR"])),
+                    forbidden: HashSet::new(),
+                },
+            ],
+        };
+        assert_eq!(
+            message.lint_context(&rules),
+            vec![
+                // the forbidden list take precedence over the allowed list of the same rule
+                (
+                    0x10,
+                    ContextViolation::Forbidden {
+                        placeholder: "CS".to_string(),
+                        rule: 0
+                    }
+                ),
+                (
+                    0x10,
+                    ContextViolation::NotAllowed {
+                        placeholder: "CS".to_string(),
+                        rule: 1
+                    }
+                ),
+                (
+                    0x10,
+                    ContextViolation::NotAllowed {
+                        placeholder: "K".to_string(),
+                        rule: 0
+                    }
+                ),
+                (
+                    0x10,
+                    ContextViolation::NotAllowed {
+                        placeholder: "K".to_string(),
+                        rule: 1
+                    }
+                ),
+            ]
+        );
+    }
+}
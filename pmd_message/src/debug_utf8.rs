@@ -0,0 +1,55 @@
+use crate::{MessageBin, MessageBinReadError, MessageBinWriteError};
+use byteorder::{ReadBytesExt, WriteBytesExt, LE};
+use std::{
+    convert::TryInto,
+    io::{self, Read, Write},
+};
+
+/// The magic at the start of the files written by [`MessageBin::write_debug_utf8`](crate::MessageBin::write_debug_utf8).
+///
+/// It differ from the `SIR0` magic of the real files, so that a game (or [`MessageBin::load_file`](crate::MessageBin::load_file)) won't load them.
+pub const DEBUG_UTF8_MAGIC: [u8; 8] = *b"PMDMSGU8";
+
+/// Write the messages in the debug UTF-8 format: the magic, the number of messages, then for each message its hash, its unknown value,
+/// the length of its text in bytes and the UTF-8 text itself (without terminator). All the numbers are 4 bytes little endian.
+pub(crate) fn write_debug_utf8<T: Write>(
+    message_bin: &MessageBin,
+    file: &mut T,
+) -> Result<(), MessageBinWriteError> {
+    file.write_all(&DEBUG_UTF8_MAGIC)?;
+    file.write_u32::<LE>(message_bin.messages().len().try_into()?)?;
+    for (hash, unk, text) in message_bin.messages() {
+        file.write_u32::<LE>(*hash)?;
+        file.write_u32::<LE>(*unk)?;
+        file.write_u32::<LE>(text.len().try_into()?)?;
+        file.write_all(text.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Read a file written by [`write_debug_utf8`]
+pub(crate) fn load_debug_utf8<T: Read>(file: &mut T) -> Result<MessageBin, MessageBinReadError> {
+    let mut magic = [0; 8];
+    file.read_exact(&mut magic)?;
+    if magic != DEBUG_UTF8_MAGIC {
+        return Err(MessageBinReadError::InvalidDebugMagic(magic));
+    };
+    let count = file.read_u32::<LE>()?;
+    let mut message_bin = MessageBin::default();
+    for _ in 0..count {
+        let hash = file.read_u32::<LE>()?;
+        let unk = file.read_u32::<LE>()?;
+        let len = file.read_u32::<LE>()?;
+        let mut text = Vec::new();
+        file.by_ref().take(len as u64).read_to_end(&mut text)?;
+        if text.len() != len as usize {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        };
+        let text = String::from_utf8(text).map_err(|err| MessageBinReadError::InvalidUtf8 {
+            hash,
+            source: err.utf8_error(),
+        })?;
+        message_bin.insert(hash, unk, text);
+    }
+    Ok(message_bin)
+}
@@ -0,0 +1,19 @@
+/// The differences between two [`MessageBin`](crate::MessageBin), as returned by [`MessageBin::diff`](crate::MessageBin::diff).
+///
+/// Each list contain hashes, in the order of the file they are present in.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MessageBinDiff {
+    /// Hashes only present in the original file
+    pub removed: Vec<u32>,
+    /// Hashes only present in the other file
+    pub added: Vec<u32>,
+    /// Hashes present in both files, but whose text or unknown value differ
+    pub changed: Vec<u32>,
+}
+
+impl MessageBinDiff {
+    /// Return true if both files contain the same messages
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.added.is_empty() && self.changed.is_empty()
+    }
+}
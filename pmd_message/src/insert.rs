@@ -0,0 +1,18 @@
+/// The result of [`MessageBin::insert_dedup`](crate::MessageBin::insert_dedup)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InsertOutcome {
+    /// There was no message with this hash. It was added at the end of the messages list.
+    Added,
+    /// A message with the same hash, unknown value and text was already present. Nothing changed.
+    Duplicate,
+    /// A message with this hash was present with a different unknown value or text. It was replaced, and those are the old values.
+    Replaced { unk: u32, text: String },
+}
+
+/// Multiple labels that have the same hash, as reported by [`MessageBin::from_labels`](crate::MessageBin::from_labels)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelCollision {
+    pub hash: u32,
+    /// The labels with this hash, in the order they were inserted. Only the message of the last one is kept.
+    pub labels: Vec<String>,
+}
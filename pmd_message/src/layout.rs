@@ -0,0 +1,78 @@
+/// An irregularity in the layout of the string block of a file, as reported by [`MessageBin::check_string_layout`](crate::MessageBin::check_string_layout).
+///
+/// Offsets and lengths are in bytes, from the start of the file. The 4 bytes alignment padding at the end of the string block isn't reported.
+/// Null code units following a string (additional terminators or per-string alignment) are counted as part of this string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StringLayoutAnomaly {
+    /// Some bytes of the string block are not part of any string
+    Gap { start: u64, length: u64 },
+    /// Some bytes are part of multiple strings (or of a string and the metadata that follow the string block)
+    Overlap {
+        start: u64,
+        length: u64,
+        hashes: Vec<u32>,
+    },
+    /// Multiple metadata entries point to the same string. This is likely an intentional deduplication, and isn't an error.
+    SharedOffset { offset: u64, hashes: Vec<u32> },
+}
+
+impl StringLayoutAnomaly {
+    /// Return true if the anomaly isn't just informative
+    pub fn is_error(&self) -> bool {
+        !matches!(self, Self::SharedOffset { .. })
+    }
+}
+
+/// A labeled part of a file, as returned by [`MessageBin::layout_regions`](crate::MessageBin::layout_regions).
+///
+/// Offsets and lengths are in bytes, from the start of the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutRegion {
+    pub start: u64,
+    pub length: u64,
+    pub kind: LayoutRegionKind,
+}
+
+/// What a [`LayoutRegion`] contains
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayoutRegionKind {
+    /// The header of the Sir0 container, and its padding
+    Sir0Header,
+    /// A null terminated string, pointed to by the metadata of all those hashes
+    String { hashes: Vec<u32> },
+    /// A metadata entry (pointer to the string, hash and unk value, each of 4 bytes)
+    StringMetadata { hash: u32 },
+    /// The message header (number of strings and pointer to the metadata, each of 4 bytes)
+    Header,
+    /// The list of pointers of the Sir0 container, up to the end of the file
+    Sir0Footer,
+    /// Bytes not part of any other region
+    Padding,
+}
+
+/// The sizes and offsets of a file, as returned by [`MessageBin::file_info`](crate::MessageBin::file_info).
+///
+/// Offsets and sizes are in bytes, offsets are from the start of the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageBinFileInfo {
+    /// The size of the whole file
+    pub total_size: u64,
+    /// The size of the string block, from the end of the Sir0 header to the metadata (including the alignment padding)
+    pub string_block_size: u64,
+    /// The size of the string metadata (12 bytes per string)
+    pub metadata_size: u64,
+    /// The offset of the message header (number of strings and pointer to the metadata)
+    pub header_offset: u64,
+    /// The offset of the Sir0 footer
+    pub footer_offset: u64,
+}
+
+/// The position of the string of a message in a file, as returned by [`MessageBin::string_offsets`](crate::MessageBin::string_offsets).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringOffset {
+    pub hash: u32,
+    /// The offset of the string, in bytes, from the start of the file
+    pub offset: u64,
+    /// The number of bytes up to the next string (or to the end of the string block for the last one), including the terminator and any padding
+    pub length: u64,
+}
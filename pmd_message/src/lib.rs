@@ -1,17 +1,75 @@
 use binread::{BinRead, BinReaderExt, NullWideString};
 use binwrite::BinWrite;
-use byteorder::{WriteBytesExt, LE};
-use pmd_code_table::{CodeToText, CodeToTextError, TextToCode, TextToCodeError};
-use pmd_sir0::{write_sir0_footer, write_sir0_header, Sir0, Sir0Error, Sir0WriteFooterError};
+use pmd_sir0::{Sir0, Sir0Error, Sir0WriteFooterError};
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet, HashSet},
     convert::TryInto,
     io::{self, Cursor, Read, Seek, SeekFrom, Write},
     num::TryFromIntError,
-    u32,
 };
 use thiserror::Error;
 
+/// Emit a [`tracing`] event when the `tracing` feature is enabled. Expand to nothing otherwise.
+macro_rules! trace_event {
+    ($level:ident, $($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($arg)*);
+    };
+}
+
+mod coding;
+pub use coding::{CodingError, Decoder, Encoder, TextMode};
+
+mod context;
+pub use context::{ContextRule, ContextRules, ContextScope, ContextViolation};
+
+mod options;
+pub use options::{Endianness, ErrorAction, MessageBinReadOptions, MessageBinWriteOptions};
+
+mod writer;
+pub use writer::MessageBinWriter;
+
+mod diff;
+pub use diff::MessageBinDiff;
+
+mod layout;
+pub use layout::{
+    LayoutRegion, LayoutRegionKind, MessageBinFileInfo, StringLayoutAnomaly, StringOffset,
+};
+
+mod raw_message;
+pub use raw_message::{RawMessage, CONTROL_CODE_RANGE};
+
+mod roundtrip;
+pub use roundtrip::{RoundtripError, RoundtripReport, ROUNDTRIP_CONTEXT_LEN};
+
+mod segment;
+pub use segment::{reassemble, segment_for_translation, split_pages, Segment};
+
+mod shared;
+pub use shared::{common_hashes, shared_texts};
+
+#[cfg(feature = "serde")]
+mod serde_impl;
+
+mod message;
+pub use message::{Iter, Message};
+
+mod insert;
+pub use insert::{InsertOutcome, LabelCollision};
+
+mod line_break;
+pub use line_break::{LineBreakMode, LINE_BREAK_MODE_MASK};
+
+mod load_iter;
+pub use load_iter::MessageIter;
+
+mod stats;
+pub use stats::MessageBinStats;
+
+mod debug_utf8;
+pub use debug_utf8::DEBUG_UTF8_MAGIC;
+
 /// An error that may occur when reading a [`MessageBin`] file via [`MessageBin::load_file`]
 #[derive(Error, Debug)]
 pub enum MessageBinReadError {
@@ -19,13 +77,70 @@ pub enum MessageBinReadError {
     IOError(#[from] io::Error),
     #[error("an error occured when reding the Sir0 part of the file")]
     Sir0Error(#[from] Sir0Error),
+    /// The file doesn't start with the `SIR0` magic, so it isn't a messagebin file. If the file is shorter than 4 bytes, the
+    /// missing bytes of `found` are 0.
+    ///
+    /// ```
+    /// use pmd_message::{MessageBin, MessageBinReadError};
+    ///
+    /// let data = [0x4E, 0x12, 0xA7, 0x03, 0xFF, 0x00, 0x91, 0x5C, 0x22, 0x8B, 0x00, 0x00, 0x17, 0xE4, 0x60, 0x3D];
+    /// assert!(matches!(
+    ///     MessageBin::from_bytes(&data, None),
+    ///     Err(MessageBinReadError::NotSir0 { found: [0x4E, 0x12, 0xA7, 0x03] })
+    /// ));
+    /// ```
+    #[error(
+        "the file doesn't start with the SIR0 magic (found {found:?}), it isn't a messagebin file"
+    )]
+    NotSir0 { found: [u8; 4] },
     #[error("a binread error occured")]
     BinReadError(#[from] binread::Error),
-    #[error("can't decode the string {1:?}")]
-    CantDecodeString(#[source] CodeToTextError, String),
+    #[error("can't decode the string of the message with the hash {hash} (at the offset {offset}): {text:?}")]
+    CantDecodeString {
+        hash: u32,
+        offset: u64,
+        /// The string interpreted as UTF-16
+        text: String,
+        #[source]
+        source: CodingError,
+    },
+    #[error("the decoded texts exceed the limit of {limit} bytes")]
+    DecodedSizeExceeded { limit: u64 },
+    #[error("reading the file require more work than allowed by the read options")]
+    BudgetExceeded,
+    #[error("multiple messages have the hash {0}")]
+    DuplicateHash(u32),
+    #[error("the file doesn't start with the debug UTF-8 magic (found {0:?})")]
+    InvalidDebugMagic([u8; 8]),
+    #[error("the text of the message with the hash {hash} isn't valid UTF-8")]
+    InvalidUtf8 {
+        hash: u32,
+        #[source]
+        source: std::str::Utf8Error,
+    },
 }
 
 /// An error that may occur when writing a [`MessageBin`] file via [`Messagebin::write`]
+///
+/// ```
+/// use pmd_message::{CodingError, Encoder, MessageBin, MessageBinWriteError};
+/// use std::io::Cursor;
+///
+/// struct AsciiOnly;
+///
+/// impl Encoder for AsciiOnly {
+///     fn encode(&self, text: &str) -> Result<Vec<u16>, CodingError> {
+///         if !text.is_ascii() {
+///             return Err("not ascii".into());
+///         }
+///         Ok(text.encode_utf16().collect())
+///     }
+/// }
+///
+/// let message = MessageBin::from_messages(vec![(1, 0, "ok".to_string()), (2, 0, "é".to_string())]);
+/// let result = message.write(&mut Cursor::new(Vec::new()), Some(&AsciiOnly));
+/// assert!(matches!(result, Err(MessageBinWriteError::CantEncodeText { hash: 2, .. })));
+/// ```
 #[derive(Error, Debug)]
 pub enum MessageBinWriteError {
     #[error("an input/output error occured")]
@@ -36,20 +151,44 @@ pub enum MessageBinWriteError {
     Overflow,
     #[error("an error occured writing the sir0 footer")]
     Sir0WriteFooterError(#[from] Sir0WriteFooterError),
-    #[error("Can't transform a human text into a encoded string (may be related to invalid label in the source text). Source text : {1:?}")]
-    CantEncodeText(#[source] TextToCodeError, String),
+    #[error("Can't transform the human text of the message with the hash {hash} into a encoded string (may be related to invalid label in the source text). Source text : {text:?}")]
+    CantEncodeText {
+        hash: u32,
+        text: String,
+        #[source]
+        source: CodingError,
+    },
+    #[error("the encoded message with the hash {hash} contain a null character at the code unit {position}. It would be cut at this point, as nulls terminate strings")]
+    EmbeddedNull { hash: u32, position: usize },
+    #[error("the string block should end at the offset {expected}, but the writer is at {found}. The file should be empty when the writing start")]
+    UnexpectedPosition { expected: u64, found: u64 },
+    #[error("the string alignment should be 0, 1 or an even power of two up to 16, but it is {0}")]
+    InvalidStringAlignment(u32),
+    #[error("the texts are {expected:?}, but they are written as if they were {found:?} (check whether a code table should be used)")]
+    TextModeMismatch { expected: TextMode, found: TextMode },
+    #[error("can't encode the message with the hash {hash} with the {table} code table")]
+    CantEncodeWithTable {
+        hash: u32,
+        table: &'static str,
+        #[source]
+        source: CodingError,
+    },
+}
+
+/// An error that may occur when replacing the texts of a [`MessageBin`] via [`MessageBin::apply_ordered_texts`]
+#[derive(Error, Debug)]
+pub enum ApplyError {
+    #[error("there are {found} texts, but the file contain {expected} messages")]
+    CountMismatch { expected: usize, found: usize },
 }
 
 #[derive(BinRead, Debug)]
-#[br(little)]
 struct MessageBinSir0Header {
     string_count: u32,
     string_info_pointer: u32,
 }
 
 #[derive(BinRead, Debug, BinWrite)]
-#[br(little)]
-#[binwrite(little)]
 struct MessageBinStringData {
     string_pointer: u32,
     string_hash: u32,
@@ -57,7 +196,6 @@ struct MessageBinStringData {
 }
 
 #[derive(BinRead, Debug)]
-#[br(little)]
 struct MessageBinText {
     text: NullWideString,
 }
@@ -65,20 +203,171 @@ struct MessageBinText {
 /// A structure representing a translation (message) file in 3ds pokemon mystery dungeon games.
 ///
 /// Each text have an associated (32bit, probably crc32) hash associated with them as a key.
+///
+/// A valid file can be built from scratch (even an empty one):
+///
+/// ```
+/// use pmd_message::MessageBin;
+/// use std::io::Cursor;
+///
+/// let mut message = MessageBin::default();
+/// message.insert(0x1234, 1, "Hello".to_string());
+/// message.insert(0x0042, 2, "World".to_string());
+///
+/// let mut file = Cursor::new(Vec::new());
+/// message.write(&mut file, None).unwrap();
+///
+/// let reloaded = MessageBin::load_file(&mut file, None).unwrap();
+/// assert_eq!(reloaded.messages(), message.messages());
+/// ```
 #[derive(Debug, Default)] //TODO: maybe there is a library for this kind of data structure (map sorted with addition order)
 pub struct MessageBin {
     /// Contain a reference to the index of an image stored in this file, indexed by the id (an hash)
     hash_to_id: BTreeMap<u32, usize>,
     /// Contain the list of message, in the order of the file, with it's hash, an unknown value and content
     message: Vec<(u32, u32, String)>,
+    /// Contain the number of additional null code units written after the terminator of a message, indexed by hash. Absent when there are none.
+    trailing_nulls: BTreeMap<u32, u16>,
+    /// The sizes and offsets of the file this was loaded from, if any
+    file_info: Option<MessageBinFileInfo>,
+    /// Whether the texts were decoded when loaded. `None` if unknown, which disable the check in [`MessageBin::write`].
+    text_mode: Option<TextMode>,
+    /// The order in which each message was first added (when loading, this is the order of the file), indexed by hash. Used by [`MessageBin::sort_by_insertion`].
+    insertion_rank: BTreeMap<u32, u64>,
+    /// The rank the next added message will get
+    next_insertion_rank: u64,
 }
 
 impl MessageBin {
+    /// Build a [`MessageBin`] from hash, unknown value and text entries, in this order.
+    ///
+    /// As with [`MessageBin::insert`], a later entry with an already present hash replace the earlier one, keeping its position.
+    ///
+    /// ```
+    /// use pmd_message::MessageBin;
+    ///
+    /// let message = MessageBin::from_messages(vec![
+    ///     (0x30, 1, "first".to_string()),
+    ///     (0x10, 2, "second".to_string()),
+    ///     (0x30, 3, "replaced".to_string()),
+    /// ]);
+    /// assert_eq!(
+    ///     message.messages(),
+    ///     &vec![(0x30, 3, "replaced".to_string()), (0x10, 2, "second".to_string())]
+    /// );
+    /// ```
+    pub fn from_messages(entries: impl IntoIterator<Item = (u32, u32, String)>) -> Self {
+        let entries = entries.into_iter();
+        let mut message_bin = MessageBin {
+            message: Vec::with_capacity(entries.size_hint().0),
+            ..Default::default()
+        };
+        for (hash, unk, text) in entries {
+            message_bin.insert(hash, unk, text);
+        }
+        message_bin
+    }
+
+    /// Build a [`MessageBin`] from [`Message`]s, like [`MessageBin::from_messages`].
+    pub fn from_named(messages: Vec<Message>) -> Self {
+        Self::from_messages(messages.into_iter().map(Into::into))
+    }
+
+    /// Return a copy of all the messages as [`Message`]s, in the order of the file.
+    pub fn messages_named(&self) -> Vec<Message> {
+        self.message.iter().cloned().map(Message::from).collect()
+    }
+
     /// Return all the hash, the unk value and message content stored in the file
     pub fn messages(&self) -> &Vec<(u32, u32, String)> {
         &self.message
     }
 
+    /// Iterate over the hash, unknown value and text of all the messages, in the order of the file.
+    ///
+    /// ```
+    /// use pmd_message::MessageBin;
+    ///
+    /// let message = MessageBin::from_messages(vec![(0x30, 1, "a".to_string()), (0x10, 2, "b".to_string())]);
+    /// let borrowed: Vec<(u32, u32, &str)> = message.iter().collect();
+    /// assert_eq!(borrowed, vec![(0x30, 1, "a"), (0x10, 2, "b")]);
+    /// for (hash, _, text) in &message {
+    ///     assert_eq!(message.message_by_hash(hash).unwrap(), text);
+    /// }
+    /// let owned: Vec<(u32, u32, String)> = message.into_iter().collect();
+    /// assert_eq!(owned, vec![(0x30, 1, "a".to_string()), (0x10, 2, "b".to_string())]);
+    /// ```
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            inner: self.message.iter(),
+        }
+    }
+
+    /// Iterate over the hashes of all the messages, in the order of the file.
+    ///
+    /// ```
+    /// use pmd_message::MessageBin;
+    ///
+    /// let mut message = MessageBin::default();
+    /// message.insert(0x30, 0, "a".to_string());
+    /// message.insert(0x10, 0, "b".to_string());
+    /// message.insert(0x20, 0, "c".to_string());
+    /// assert_eq!(message.keys().collect::<Vec<u32>>(), vec![0x30, 0x10, 0x20]);
+    /// assert_eq!(message.values().collect::<Vec<&str>>(), vec!["a", "b", "c"]);
+    /// ```
+    pub fn keys(&self) -> impl Iterator<Item = u32> + '_ {
+        self.message.iter().map(|(hash, _, _)| *hash)
+    }
+
+    /// Iterate over the texts of all the messages, in the order of the file.
+    pub fn values(&self) -> impl Iterator<Item = &str> + '_ {
+        self.message.iter().map(|(_, _, text)| text.as_str())
+    }
+
+    /// Return the number of messages.
+    ///
+    /// ```
+    /// use pmd_message::MessageBin;
+    ///
+    /// let mut message = MessageBin::default();
+    /// assert!(message.is_empty());
+    /// assert!(!message.contains_hash(0x1234));
+    /// message.insert(0x1234, 0, "Hello".to_string());
+    /// assert_eq!(message.len(), 1);
+    /// assert!(!message.is_empty());
+    /// assert!(message.contains_hash(0x1234));
+    /// ```
+    pub fn len(&self) -> usize {
+        self.message.len()
+    }
+
+    /// Return true if there are no messages.
+    pub fn is_empty(&self) -> bool {
+        self.message.is_empty()
+    }
+
+    /// Return true if there is a message with the given hash.
+    pub fn contains_hash(&self, hash: u32) -> bool {
+        self.hash_to_id.contains_key(&hash)
+    }
+
+    /// Return the sizes and offsets of the file this was read from by one of the `load_file` functions.
+    ///
+    /// They describe the file as it was loaded, and aren't updated when the messages are modified. Return `None` for a [`MessageBin`] built from scratch.
+    pub fn file_info(&self) -> Option<&MessageBinFileInfo> {
+        self.file_info.as_ref()
+    }
+
+    /// Return whether the texts were decoded by the `load_file` function used to load this file. `None` for a [`MessageBin`] built from scratch.
+    pub fn text_mode(&self) -> Option<TextMode> {
+        self.text_mode
+    }
+
+    /// Set what the texts contain, or `None` to disable the check done by [`MessageBin::write`].
+    pub fn set_text_mode(&mut self, text_mode: Option<TextMode>) {
+        self.text_mode = text_mode;
+    }
+
     /// Return the message content with the given hash if it exist.
     pub fn message_by_hash(&self, hash: u32) -> Option<&String> {
         match self.hash_to_id.get(&hash) {
@@ -87,6 +376,323 @@ impl MessageBin {
         }
     }
 
+    /// Return a mutable reference to the message content with the given hash if it exist.
+    ///
+    /// ```
+    /// use pmd_message::MessageBin;
+    ///
+    /// let mut message = MessageBin::from_messages(vec![(0x1234, 1, "Hello [NAME]".to_string())]);
+    /// let text = message.message_by_hash_mut(0x1234).unwrap();
+    /// *text = text.replace("[NAME]", "[CS:A]");
+    /// assert_eq!(message.message_by_hash(0x1234).unwrap(), "Hello [CS:A]");
+    /// ```
+    pub fn message_by_hash_mut(&mut self, hash: u32) -> Option<&mut String> {
+        let id = *self.hash_to_id.get(&hash)?;
+        Some(&mut self.message[id].2)
+    }
+
+    /// Return the unknown value of the message with the given hash if it exist.
+    ///
+    /// ```
+    /// use pmd_message::MessageBin;
+    ///
+    /// let mut message = MessageBin::default();
+    /// message.insert(0x1234, 7, "Hello".to_string());
+    /// assert_eq!(message.unk_by_hash(0x1234), Some(7));
+    /// assert_eq!(message.unk_by_hash(0x5678), None);
+    /// ```
+    pub fn unk_by_hash(&self, hash: u32) -> Option<u32> {
+        self.hash_to_id.get(&hash).map(|id| self.message[*id].1)
+    }
+
+    /// Return a mutable reference to the unknown value of the message with the given hash if it exist.
+    pub fn unk_by_hash_mut(&mut self, hash: u32) -> Option<&mut u32> {
+        let id = *self.hash_to_id.get(&hash)?;
+        Some(&mut self.message[id].1)
+    }
+
+    /// Return the [`LineBreakMode`] stored in the unknown value of the message with the given hash, if it exist.
+    pub fn line_break_mode(&self, hash: u32) -> Option<LineBreakMode> {
+        self.hash_to_id
+            .get(&hash)
+            .map(|id| LineBreakMode::from_unk(self.message[*id].1))
+    }
+
+    /// Store the [`LineBreakMode`] in the unknown value of the message with the given hash, keeping its other bits.
+    /// Return false if there is no such message.
+    pub fn set_line_break_mode(&mut self, hash: u32, mode: LineBreakMode) -> bool {
+        match self.hash_to_id.get(&hash) {
+            None => false,
+            Some(id) => {
+                let unk = &mut self.message[*id].1;
+                *unk = mode.apply_to_unk(*unk);
+                true
+            }
+        }
+    }
+
+    /// Return the text of the messages with the given hashes, indexed by hash. Hashes not present in this file are ignored.
+    pub fn export_subset_map(&self, hashes: &[u32]) -> BTreeMap<u32, String> {
+        hashes
+            .iter()
+            .filter_map(|hash| {
+                self.message_by_hash(*hash)
+                    .map(|text| (*hash, text.clone()))
+            })
+            .collect()
+    }
+
+    /// Return a new [`MessageBin`] containing only the message with the given hash (with its trailing nulls), if it exist.
+    ///
+    /// Once written, it is a minimal file to reproduce an issue with this message.
+    pub fn isolate(&self, hash: u32) -> Option<MessageBin> {
+        let (_, unk, text) = &self.message[*self.hash_to_id.get(&hash)?];
+        let mut isolated = MessageBin::default();
+        isolated.insert(hash, *unk, text.clone());
+        isolated.set_trailing_nulls(hash, self.trailing_nulls(hash));
+        isolated.text_mode = self.text_mode;
+        Some(isolated)
+    }
+
+    /// Return all the hash whose `prefix_bits` most significant bits are equal to `prefix`, in ascending order.
+    ///
+    /// `prefix` is right-aligned: to search for hashes starting with `0xAB`, use a prefix of `0xAB` with 8 bits.
+    /// A `prefix_bits` of 0 match every hash, and a `prefix_bits` of 32 or more require an exact match.
+    pub fn find_by_hash_prefix(&self, prefix: u32, prefix_bits: u32) -> Vec<u32> {
+        let (start, end) = match prefix_bits {
+            0 => (0, u32::MAX),
+            1..=31 => {
+                let shift = 32 - prefix_bits;
+                let start = prefix << shift;
+                if start >> shift != prefix {
+                    return Vec::new();
+                };
+                (start, start | (u32::MAX >> prefix_bits))
+            }
+            _ => (prefix, prefix),
+        };
+        self.hash_to_id
+            .range(start..=end)
+            .map(|(hash, _)| *hash)
+            .collect()
+    }
+
+    /// Return the hash and text of all the messages whose hash is in `start..end`, in ascending hash order.
+    pub fn messages_in_range(&self, start: u32, end: u32) -> Vec<(u32, &str)> {
+        if start >= end {
+            return Vec::new();
+        };
+        self.hash_to_id
+            .range(start..end)
+            .map(|(hash, id)| (*hash, self.message[*id].2.as_str()))
+            .collect()
+    }
+
+    /// Compare this file with another one, listing the messages that were removed, added or changed in `other`.
+    pub fn diff(&self, other: &MessageBin) -> MessageBinDiff {
+        let mut diff = MessageBinDiff::default();
+        for (hash, unk, text) in self.message.iter() {
+            match other.hash_to_id.get(hash) {
+                None => diff.removed.push(*hash),
+                Some(position) => {
+                    let (_, other_unk, other_text) = &other.message[*position];
+                    if unk != other_unk || text != other_text {
+                        diff.changed.push(*hash);
+                    }
+                }
+            }
+        }
+        for (hash, _, _) in other.message.iter() {
+            if !self.hash_to_id.contains_key(hash) {
+                diff.added.push(*hash);
+            }
+        }
+        diff
+    }
+
+    /// Return the hashes of the messages whose text is identical to the one with the same hash in `reference`, in the order of this file.
+    pub fn unchanged_against(&self, reference: &MessageBin) -> Vec<u32> {
+        self.message
+            .iter()
+            .filter(|(hash, _, text)| reference.message_by_hash(*hash) == Some(text))
+            .map(|(hash, _, _)| *hash)
+            .collect()
+    }
+
+    /// Replace the text of every message with the text at the same position in `texts`, keeping the hashes and unknown values.
+    ///
+    /// Fail without changing anything if there isn't exactly one text per message.
+    pub fn apply_ordered_texts(&mut self, texts: &[String]) -> Result<(), ApplyError> {
+        if texts.len() != self.message.len() {
+            return Err(ApplyError::CountMismatch {
+                expected: self.message.len(),
+                found: texts.len(),
+            });
+        };
+        for ((_, _, text), new_text) in self.message.iter_mut().zip(texts.iter()) {
+            text.clone_from(new_text);
+        }
+        Ok(())
+    }
+
+    /// Replace every occurrence of the character `from` by `to` in all the messages, and return the number of replaced characters.
+    pub fn replace_code_point(&mut self, from: char, to: char) -> usize {
+        let mut count = 0;
+        for (_, _, text) in self.message.iter_mut() {
+            let occurrences = text.matches(from).count();
+            if occurrences > 0 {
+                *text = text.replace(from, to.encode_utf8(&mut [0; 4]));
+                count += occurrences;
+            }
+        }
+        count
+    }
+
+    /// Apply `f` to the text of every message whose hash satisfy `pred`, and return the number of messages whose text changed.
+    ///
+    /// Hashes, unknown values and the order of the messages are kept.
+    pub fn map_where<P: Fn(u32) -> bool, F: FnMut(&mut String)>(
+        &mut self,
+        pred: P,
+        mut f: F,
+    ) -> usize {
+        let mut count = 0;
+        for (hash, _, text) in self.message.iter_mut() {
+            if pred(*hash) {
+                let original = text.clone();
+                f(text);
+                if *text != original {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    /// Return the hash and number of lines of every message with more than `max_lines` lines, in the order of the file.
+    ///
+    /// Lines are separated by `\n` and by `line_break`, the placeholder the code table decode the line break control code to
+    /// (an empty `line_break` only count `\n`). A message with no line break has one line.
+    pub fn lint_line_count(&self, max_lines: usize, line_break: &str) -> Vec<(u32, usize)> {
+        self.message
+            .iter()
+            .map(|(hash, _, text)| {
+                let line_breaks: usize = segment_for_translation(text)
+                    .iter()
+                    .map(|segment| match segment {
+                        Segment::Translatable(text) => text.matches('\n').count(),
+                        Segment::Protected(placeholder) => {
+                            usize::from(!line_break.is_empty() && placeholder == line_break)
+                        }
+                    })
+                    .sum();
+                (*hash, line_breaks + 1)
+            })
+            .filter(|(_, line_count)| *line_count > max_lines)
+            .collect()
+    }
+
+    /// Check the placeholders of all the messages against the rules, and return the violations with the hash of the message, in the order of the file.
+    pub fn lint_context(&self, rules: &ContextRules) -> Vec<(u32, ContextViolation)> {
+        let mut result = Vec::new();
+        for (hash, unk, text) in self.message.iter() {
+            let segments = segment_for_translation(text);
+            let placeholders: Vec<&str> = segments
+                .iter()
+                .filter_map(Segment::placeholder_name)
+                .collect();
+            for violation in rules.check(*hash, *unk, &placeholders) {
+                result.push((*hash, violation));
+            }
+        }
+        result
+    }
+
+    /// Compute statistics on the messages, like their number and the placeholders they use.
+    ///
+    /// ```
+    /// use pmd_message::MessageBin;
+    ///
+    /// let message = MessageBin::from_messages(vec![
+    ///     (0x30, 0, "Hi [CS:A]!".to_string()),
+    ///     (0x10, 0, "".to_string()),
+    ///     (0x20, 0, "[CS:B][CS:A][CR]".to_string()),
+    /// ]);
+    /// let stats = message.stats();
+    /// assert_eq!(stats.message_count, 3);
+    /// assert_eq!(stats.empty_count, 1);
+    /// assert_eq!(stats.placeholder_messages["CS"], 2);
+    /// assert_eq!(stats.placeholder_messages["CR"], 1);
+    /// assert_eq!((stats.min_hash, stats.max_hash), (Some(0x10), Some(0x30)));
+    /// ```
+    pub fn stats(&self) -> MessageBinStats {
+        let mut stats = MessageBinStats {
+            message_count: self.message.len(),
+            min_hash: self.hash_to_id.keys().next().copied(),
+            max_hash: self.hash_to_id.keys().next_back().copied(),
+            ..Default::default()
+        };
+        for (_, _, text) in self.message.iter() {
+            if text.is_empty() {
+                stats.empty_count += 1;
+            };
+            stats.char_count += text.chars().count();
+            let segments = segment_for_translation(text);
+            let placeholders: BTreeSet<&str> = segments
+                .iter()
+                .filter_map(Segment::placeholder_name)
+                .collect();
+            for placeholder in placeholders {
+                *stats
+                    .placeholder_messages
+                    .entry(placeholder.to_string())
+                    .or_default() += 1;
+            }
+        }
+        stats
+    }
+
+    /// Split the messages into one [`MessageBin`] per distinct unknown value, keeping their relative order.
+    ///
+    /// Like with [`MessageBin::isolate`], each group keep the text mode of this file and the trailing nulls of its messages.
+    pub fn group_by_unk(&self) -> BTreeMap<u32, MessageBin> {
+        let mut groups: BTreeMap<u32, MessageBin> = BTreeMap::new();
+        for (hash, unk, text) in self.message.iter() {
+            let group = groups.entry(*unk).or_insert_with(|| MessageBin {
+                text_mode: self.text_mode,
+                ..Default::default()
+            });
+            group.insert(*hash, *unk, text.clone());
+            group.set_trailing_nulls(*hash, self.trailing_nulls(*hash));
+        }
+        groups
+    }
+
+    /// Return the number of additional null code units written after the null terminator of the message with the given hash.
+    ///
+    /// Some files terminate some strings with more than one null. Those are detected by [`MessageBin::load_file`] so that [`MessageBin::write`] reproduce them.
+    pub fn trailing_nulls(&self, hash: u32) -> u16 {
+        self.trailing_nulls.get(&hash).copied().unwrap_or(0)
+    }
+
+    /// Set the number of additional null code units to write after the null terminator of the message with the given hash.
+    pub fn set_trailing_nulls(&mut self, hash: u32, count: u16) {
+        if count == 0 {
+            self.trailing_nulls.remove(&hash);
+        } else {
+            self.trailing_nulls.insert(hash, count);
+        }
+    }
+
+    /// Return the hashes of the messages whose text contain the given character, in the order of the file.
+    pub fn messages_using_code(&self, code: char) -> Vec<u32> {
+        self.message
+            .iter()
+            .filter(|(_, _, text)| text.contains(code))
+            .map(|(hash, _, _)| *hash)
+            .collect()
+    }
+
     /// If the hash is already present, update the message content and unknown value, otherwise, add a new message at the end of the messages list.
     /// Return the old string if it exist.
     pub fn insert(&mut self, hash: u32, unk: u32, message: String) {
@@ -95,6 +701,8 @@ impl MessageBin {
                 let position = self.message.len();
                 self.message.push((hash, unk, message));
                 self.hash_to_id.insert(hash, position);
+                self.insertion_rank.insert(hash, self.next_insertion_rank);
+                self.next_insertion_rank += 1;
             }
             Some(position) => {
                 self.message[*position].1 = unk;
@@ -103,141 +711,1541 @@ impl MessageBin {
         }
     }
 
+    /// Compute the hash of a message from its label, with the standard CRC-32 (ISO-HDLC, as used by zlib) of its bytes.
+    ///
+    /// That the games use this function is still a supposition: it wasn't verified against any label and hash pair from a game file,
+    /// only against the standard check value of CRC-32 below. Use [`MessageBin::verify_hashes`] to check it with known labels.
+    ///
+    /// ```
+    /// use pmd_message::MessageBin;
+    ///
+    /// assert_eq!(MessageBin::compute_hash("123456789"), 0xCBF4_3926);
+    /// assert_eq!(MessageBin::compute_hash(""), 0);
+    /// ```
+    pub fn compute_hash(label: &str) -> u32 {
+        let mut crc = u32::MAX;
+        for byte in label.bytes() {
+            crc ^= byte as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 == 1 {
+                    (crc >> 1) ^ 0xEDB8_8320
+                } else {
+                    crc >> 1
+                };
+            }
+        }
+        !crc
+    }
+
+    /// Return the hashes of the messages that have a label in `labels` (indexed by the expected hash) whose
+    /// [`MessageBin::compute_hash`] differ from this hash, in the order of the file. Messages without a label are ignored.
+    ///
+    /// ```
+    /// use pmd_message::MessageBin;
+    /// use std::collections::BTreeMap;
+    ///
+    /// let message = MessageBin::from_messages(vec![
+    ///     (0xCBF4_3926, 0, "good".to_string()),
+    ///     (0x0000_0001, 0, "wrong".to_string()),
+    ///     (0x0000_0002, 0, "unlabeled".to_string()),
+    /// ]);
+    /// let mut labels = BTreeMap::new();
+    /// labels.insert(0xCBF4_3926, "123456789".to_string());
+    /// labels.insert(0x0000_0001, "MENU_MAIN_START".to_string());
+    /// assert_eq!(message.verify_hashes(&labels), vec![0x0000_0001]);
+    /// ```
+    pub fn verify_hashes(&self, labels: &BTreeMap<u32, String>) -> Vec<u32> {
+        self.message
+            .iter()
+            .filter_map(|(hash, _, _)| {
+                let label = labels.get(hash)?;
+                if Self::compute_hash(label) != *hash {
+                    Some(*hash)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Insert a message (like [`MessageBin::insert`]) with the hash computed from its label by [`MessageBin::compute_hash`].
+    pub fn insert_by_label(&mut self, label: &str, unk: u32, message: String) {
+        self.insert(Self::compute_hash(label), unk, message)
+    }
+
+    /// Build a [`MessageBin`] from `(label, unk, text)` entries, inserted with [`MessageBin::insert_by_label`].
+    ///
+    /// Different labels with the same hash would silently replace each other, so they are also returned, sorted by hash.
+    /// A label present multiple times only replace its previous message, and isn't a collision.
+    ///
+    /// ```
+    /// use pmd_message::{LabelCollision, MessageBin};
+    ///
+    /// let (message, collisions) = MessageBin::from_labels(vec![
+    ///     ("plumless", 0, "first".to_string()),
+    ///     ("MENU_MAIN_START", 0, "Start".to_string()),
+    ///     ("buckeroo", 0, "second".to_string()),
+    /// ]);
+    /// assert_eq!(message.len(), 2);
+    /// assert_eq!(message.message_by_hash(0x4DDB_0C25).unwrap(), "second");
+    /// assert_eq!(
+    ///     collisions,
+    ///     vec![LabelCollision {
+    ///         hash: 0x4DDB_0C25,
+    ///         labels: vec!["plumless".to_string(), "buckeroo".to_string()],
+    ///     }]
+    /// );
+    /// ```
+    pub fn from_labels<L: AsRef<str>>(
+        entries: impl IntoIterator<Item = (L, u32, String)>,
+    ) -> (Self, Vec<LabelCollision>) {
+        let mut message_bin = MessageBin::default();
+        let mut labels: BTreeMap<u32, Vec<String>> = BTreeMap::new();
+        for (label, unk, text) in entries {
+            let label = label.as_ref();
+            let hash_labels = labels.entry(Self::compute_hash(label)).or_default();
+            if !hash_labels.iter().any(|previous| previous == label) {
+                hash_labels.push(label.to_string());
+            };
+            message_bin.insert_by_label(label, unk, text);
+        }
+        let collisions = labels
+            .into_iter()
+            .filter(|(_, labels)| labels.len() > 1)
+            .map(|(hash, labels)| LabelCollision { hash, labels })
+            .collect();
+        (message_bin, collisions)
+    }
+
+    /// Insert (as with [`MessageBin::insert`]) every message of `other`, with its trailing nulls. Messages already present keep
+    /// their position, and new ones are added at the end, in the order of `other`.
+    ///
+    /// ```
+    /// use pmd_message::MessageBin;
+    ///
+    /// let mut base = MessageBin::from_messages(vec![(1, 0, "one".to_string()), (2, 0, "two".to_string())]);
+    /// let patch = MessageBin::from_messages(vec![(3, 0, "three".to_string()), (2, 1, "deux".to_string())]);
+    /// base.merge(&patch);
+    /// assert_eq!(
+    ///     base.messages(),
+    ///     &vec![(1, 0, "one".to_string()), (2, 1, "deux".to_string()), (3, 0, "three".to_string())]
+    /// );
+    /// ```
+    pub fn merge(&mut self, other: &MessageBin) {
+        for (hash, unk, text) in other.message.iter() {
+            self.insert(*hash, *unk, text.clone());
+            self.set_trailing_nulls(*hash, other.trailing_nulls(*hash));
+        }
+    }
+
+    /// Like [`MessageBin::merge`], but take both files by value.
+    pub fn merge_owned(mut self, other: MessageBin) -> Self {
+        for (hash, unk, text) in other.message {
+            self.insert(hash, unk, text);
+            self.set_trailing_nulls(hash, other.trailing_nulls.get(&hash).copied().unwrap_or(0));
+        }
+        self
+    }
+
+    /// Like [`MessageBin::insert`], but tell apart a re-insertion of an identical message (which change nothing) from an overwrite.
+    ///
+    /// ```
+    /// use pmd_message::{InsertOutcome, MessageBin};
+    ///
+    /// let mut message = MessageBin::default();
+    /// assert_eq!(message.insert_dedup(0x1234, 1, "Hello".to_string()), InsertOutcome::Added);
+    /// assert_eq!(message.insert_dedup(0x1234, 1, "Hello".to_string()), InsertOutcome::Duplicate);
+    /// assert_eq!(
+    ///     message.insert_dedup(0x1234, 1, "World".to_string()),
+    ///     InsertOutcome::Replaced { unk: 1, text: "Hello".to_string() }
+    /// );
+    /// assert_eq!(message.message_by_hash(0x1234).unwrap(), "World");
+    /// ```
+    pub fn insert_dedup(&mut self, hash: u32, unk: u32, message: String) -> InsertOutcome {
+        match self.hash_to_id.get(&hash) {
+            None => {
+                self.insert(hash, unk, message);
+                InsertOutcome::Added
+            }
+            Some(position) => {
+                let entry = &mut self.message[*position];
+                if entry.1 == unk && entry.2 == message {
+                    InsertOutcome::Duplicate
+                } else {
+                    let old_unk = std::mem::replace(&mut entry.1, unk);
+                    let old_text = std::mem::replace(&mut entry.2, message);
+                    InsertOutcome::Replaced {
+                        unk: old_unk,
+                        text: old_text,
+                    }
+                }
+            }
+        }
+    }
+
+    /// Remove the message with the given hash, and return its unknown value and text if it existed.
+    ///
+    /// The following messages keep their relative order.
+    ///
+    /// ```
+    /// use pmd_message::MessageBin;
+    ///
+    /// let mut message = MessageBin::default();
+    /// message.insert(0x10, 1, "first".to_string());
+    /// message.insert(0x20, 2, "second".to_string());
+    /// message.insert(0x30, 3, "third".to_string());
+    ///
+    /// assert_eq!(message.remove_by_hash(0x20), Some((2, "second".to_string())));
+    /// assert_eq!(message.remove_by_hash(0x20), None);
+    /// assert_eq!(message.message_by_hash(0x10).unwrap(), "first");
+    /// assert_eq!(message.message_by_hash(0x30).unwrap(), "third");
+    /// ```
+    pub fn remove_by_hash(&mut self, hash: u32) -> Option<(u32, String)> {
+        let position = self.hash_to_id.remove(&hash)?;
+        let (_, unk, text) = self.message.remove(position);
+        for id in self.hash_to_id.values_mut() {
+            if *id > position {
+                *id -= 1;
+            }
+        }
+        self.trailing_nulls.remove(&hash);
+        self.insertion_rank.remove(&hash);
+        Some((unk, text))
+    }
+
+    /// Keep only the messages for which `f` (called with the hash, unknown value and text) return true, in their relative order.
+    ///
+    /// ```
+    /// use pmd_message::MessageBin;
+    ///
+    /// let mut message = MessageBin::from_messages((1..=4).map(|hash| (hash, 0, hash.to_string())));
+    /// message.retain(|hash, _, _| hash % 2 == 0);
+    /// assert_eq!(message.len(), 2);
+    /// assert_eq!(message.message_by_hash(2).unwrap(), "2");
+    /// assert_eq!(message.message_by_hash(4).unwrap(), "4");
+    /// assert_eq!(message.message_by_hash(3), None);
+    /// ```
+    pub fn retain<F: FnMut(u32, u32, &str) -> bool>(&mut self, mut f: F) {
+        self.message
+            .retain(|(hash, unk, text)| f(*hash, *unk, text));
+        self.rebuild_hash_to_id();
+        let hash_to_id = &self.hash_to_id;
+        self.trailing_nulls
+            .retain(|hash, _| hash_to_id.contains_key(hash));
+        self.insertion_rank
+            .retain(|hash, _| hash_to_id.contains_key(hash));
+    }
+
+    /// Reorder the messages by ascending hash, the order [`MessageBin::write`] use for the metadata.
+    ///
+    /// ```
+    /// use pmd_message::MessageBin;
+    ///
+    /// let mut message = MessageBin::from_messages(vec![
+    ///     (0x30, 0, "c".to_string()),
+    ///     (0x10, 0, "a".to_string()),
+    ///     (0x20, 0, "b".to_string()),
+    /// ]);
+    /// message.sort_by_hash();
+    /// let hashes: Vec<u32> = message.messages().iter().map(|(hash, _, _)| *hash).collect();
+    /// assert_eq!(hashes, vec![0x10, 0x20, 0x30]);
+    /// assert_eq!(message.message_by_hash(0x30).unwrap(), "c");
+    /// assert_eq!(message.message_by_hash(0x10).unwrap(), "a");
+    ///
+    /// message.sort_by_insertion();
+    /// let hashes: Vec<u32> = message.messages().iter().map(|(hash, _, _)| *hash).collect();
+    /// assert_eq!(hashes, vec![0x30, 0x10, 0x20]);
+    /// assert_eq!(message.message_by_hash(0x20).unwrap(), "b");
+    /// ```
+    pub fn sort_by_hash(&mut self) {
+        self.message.sort_by_key(|(hash, _, _)| *hash);
+        self.rebuild_hash_to_id();
+    }
+
+    /// Restore the order in which the messages were first added. For a loaded file, this is the order of the strings in the file.
+    ///
+    /// Replacing the text of a message with [`MessageBin::insert`] doesn't change its place, while a removed message is added back at the end.
+    pub fn sort_by_insertion(&mut self) {
+        let insertion_rank = &self.insertion_rank;
+        self.message
+            .sort_by_key(|(hash, _, _)| insertion_rank.get(hash).copied());
+        self.rebuild_hash_to_id();
+    }
+
+    fn rebuild_hash_to_id(&mut self) {
+        self.hash_to_id = self
+            .message
+            .iter()
+            .enumerate()
+            .map(|(id, (hash, _, _))| (*hash, id))
+            .collect();
+    }
+
+    /// Load a MessageBin file from its content in memory.
+    pub fn from_bytes(
+        data: &[u8],
+        code_to_text: Option<&dyn Decoder>,
+    ) -> Result<Self, MessageBinReadError> {
+        Self::load_file(&mut Cursor::new(data), code_to_text)
+    }
+
     /// Load a MessageBin file from the reader.
     pub fn load_file<T: Read + Seek>(
-        mut file: &mut T,
-        code_to_text: Option<&CodeToText>,
+        file: &mut T,
+        code_to_text: Option<&dyn Decoder>,
     ) -> Result<Self, MessageBinReadError> {
-        file.seek(SeekFrom::Start(0))?;
+        Self::load_file_with_options(file, code_to_text, &MessageBinReadOptions::default())
+    }
 
-        // read sir0
-        let sir0 = Sir0::new(&mut file)?;
+    /// Load a MessageBin file from the reader, using the given [`MessageBinReadOptions`].
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn load_file_with_options<T: Read + Seek>(
+        file: &mut T,
+        code_to_text: Option<&dyn Decoder>,
+        options: &MessageBinReadOptions,
+    ) -> Result<Self, MessageBinReadError> {
+        Self::load_strings(
+            Self::load_messages_iter(file, code_to_text, options)?,
+            |_| true,
+            |_| ErrorAction::Abort,
+            None,
+        )
+    }
 
-        let mut sir0_header_cursor = Cursor::new(sir0.get_header());
-        let sir0_header: MessageBinSir0Header = sir0_header_cursor.read_le()?;
+    /// Load as much of a MessageBin file from the reader as possible, to recover the messages of a corrupted file.
+    ///
+    /// The messages whose string can't be read (like one pointing past the end of the file, or missing its null terminator)
+    /// or decoded are skipped, and their errors are returned alongside the loaded messages. Errors in the Sir0 container,
+    /// header or metadata still fail the loading, as no message can be found without them.
+    ///
+    /// ```
+    /// use pmd_message::MessageBin;
+    ///
+    /// let message = MessageBin::from_messages(vec![(1, 0, "Hello".to_string()), (2, 0, "World".to_string())]);
+    /// let mut bytes = message.to_bytes(None).unwrap();
+    /// // make the string pointer of the second message point to the end of the file
+    /// let info = MessageBin::from_bytes(&bytes, None).unwrap().file_info().unwrap().clone();
+    /// let pointer_offset = 16 + info.string_block_size as usize + 12;
+    /// let end = bytes.len() as u32;
+    /// bytes[pointer_offset..pointer_offset + 4].copy_from_slice(&end.to_le_bytes());
+    ///
+    /// assert!(MessageBin::from_bytes(&bytes, None).is_err());
+    /// let (recovered, errors) = MessageBin::load_file_lenient(&mut std::io::Cursor::new(bytes), None).unwrap();
+    /// assert_eq!(recovered.messages(), &vec![(1, 0, "Hello".to_string())]);
+    /// assert_eq!(errors.len(), 1);
+    /// ```
+    pub fn load_file_lenient<T: Read + Seek>(
+        file: &mut T,
+        code_to_text: Option<&dyn Decoder>,
+    ) -> Result<(Self, Vec<MessageBinReadError>), MessageBinReadError> {
+        let mut errors = Vec::new();
+        let message_bin = Self::load_strings(
+            Self::load_messages_iter(file, code_to_text, &MessageBinReadOptions::default())?,
+            |_| true,
+            |_| ErrorAction::Skip,
+            Some(&mut errors),
+        )?;
+        Ok((message_bin, errors))
+    }
 
-        // read string data
-        file.seek(SeekFrom::Start(sir0_header.string_info_pointer as u64))?;
+    /// Load a MessageBin file from the reader, calling `on_error` for each message that can't be decoded to decide what to do with it.
+    pub fn load_file_with_recovery<
+        T: Read + Seek,
+        E: FnMut(&MessageBinReadError) -> ErrorAction,
+    >(
+        file: &mut T,
+        code_to_text: Option<&dyn Decoder>,
+        options: &MessageBinReadOptions,
+        on_error: E,
+    ) -> Result<Self, MessageBinReadError> {
+        Self::load_strings(
+            Self::load_messages_iter(file, code_to_text, options)?,
+            |_| true,
+            on_error,
+            None,
+        )
+    }
+
+    /// Load a MessageBin file from the reader, only reading and decoding the strings whose hash is in `filter`.
+    pub fn load_file_filtered<T: Read + Seek>(
+        file: &mut T,
+        code_to_text: Option<&dyn Decoder>,
+        filter: &HashSet<u32>,
+    ) -> Result<Self, MessageBinReadError> {
+        Self::load_strings(
+            Self::load_messages_iter(file, code_to_text, &MessageBinReadOptions::default())?,
+            |hash| filter.contains(&hash),
+            |_| ErrorAction::Abort,
+            None,
+        )
+    }
+
+    /// Read the metadata of the file, and return an iterator that read and decode its messages one at a time.
+    ///
+    /// Unlike [`MessageBin::load_file`], the decoded messages are not all kept in memory.
+    ///
+    /// ```
+    /// use pmd_message::{MessageBin, MessageBinReadOptions};
+    /// use std::io::Cursor;
+    ///
+    /// let message = MessageBin::from_messages(vec![(0x1234, 1, "Hello".to_string())]);
+    /// let mut file = Cursor::new(message.to_bytes(None).unwrap());
+    /// for entry in MessageBin::load_messages_iter(&mut file, None, &MessageBinReadOptions::default()).unwrap() {
+    ///     assert_eq!(entry.unwrap(), (0x1234, 1, "Hello".to_string()));
+    /// }
+    /// ```
+    pub fn load_messages_iter<'a, T: Read + Seek>(
+        file: &'a mut T,
+        code_to_text: Option<&'a dyn Decoder>,
+        options: &MessageBinReadOptions,
+    ) -> Result<MessageIter<'a, T>, MessageBinReadError> {
+        MessageIter::new(file, code_to_text, options)
+    }
+
+    /// Read and decode the strings of the iterator whose hash match `filter`.
+    ///
+    /// `on_error` decide what to do with strings that failed to decode. If `skipped_errors` is given, the strings that can't be
+    /// read are skipped, and their errors are added to it, along with those of the strings skipped by `on_error`.
+    fn load_strings<
+        T: Read + Seek,
+        F: Fn(u32) -> bool,
+        E: FnMut(&MessageBinReadError) -> ErrorAction,
+    >(
+        mut iter: MessageIter<'_, T>,
+        filter: F,
+        mut on_error: E,
+        mut skipped_errors: Option<&mut Vec<MessageBinReadError>>,
+    ) -> Result<Self, MessageBinReadError> {
+        let mut message_bin = MessageBin::default();
+        let reject_duplicate_hashes = iter.options().reject_duplicate_hashes;
+        while let Some(string) = iter.next_raw(&filter) {
+            let string = match (string, skipped_errors.as_mut()) {
+                (Ok(string), _) => string,
+                (
+                    Err(
+                        err @ (MessageBinReadError::IOError(_)
+                        | MessageBinReadError::BinReadError(_)),
+                    ),
+                    Some(skipped_errors),
+                ) => {
+                    skipped_errors.push(err);
+                    continue;
+                }
+                (Err(err), _) => return Err(err),
+            };
+            if reject_duplicate_hashes && message_bin.hash_to_id.contains_key(&string.hash) {
+                return Err(MessageBinReadError::DuplicateHash(string.hash));
+            };
+            let text = match iter.decode(&string) {
+                Ok(decoded) => decoded,
+                Err(err) => match on_error(&err) {
+                    ErrorAction::Skip => {
+                        if let Some(skipped_errors) = skipped_errors.as_mut() {
+                            skipped_errors.push(err);
+                        };
+                        continue;
+                    }
+                    ErrorAction::Substitute(substitute) => substitute,
+                    ErrorAction::Abort => return Err(err),
+                },
+            };
+            iter.account_decoded(&text)?;
+            message_bin.insert(string.hash, string.unk, text);
+            message_bin.set_trailing_nulls(string.hash, string.trailing_nulls);
+        }
+        trace_event!(debug, count = message_bin.message.len(), "strings read");
+
+        let endianness = iter.options().structure_endianness;
+        message_bin.text_mode = Some(iter.text_mode());
+        message_bin.file_info = Some(Self::read_file_info(
+            iter.file,
+            &iter.sir0_header,
+            endianness,
+        )?);
+        Ok(message_bin)
+    }
+
+    /// Read the position of the header and of the Sir0 footer, and the size of the file, to build its [`MessageBinFileInfo`].
+    fn read_file_info<T: Read + Seek>(
+        file: &mut T,
+        sir0_header: &MessageBinSir0Header,
+        endianness: Endianness,
+    ) -> Result<MessageBinFileInfo, MessageBinReadError> {
+        file.seek(SeekFrom::Start(4))?;
+        let header_offset = endianness.read_u32(file)? as u64;
+        let footer_offset = endianness.read_u32(file)? as u64;
+        let total_size = file.seek(SeekFrom::End(0))?;
+        Ok(MessageBinFileInfo {
+            total_size,
+            string_block_size: (sir0_header.string_info_pointer as u64).saturating_sub(16),
+            metadata_size: sir0_header.string_count as u64 * 12,
+            header_offset,
+            footer_offset,
+        })
+    }
+
+    /// Load all the hash, unk value and raw UTF-16 code units of the messages from the reader, in the order they are stored in.
+    ///
+    /// The texts are neither decoded with a code table nor converted to [`String`], and entries sharing the same hash are all kept.
+    pub fn load_file_raw_pairs<T: Read + Seek>(
+        file: &mut T,
+    ) -> Result<Vec<(u32, u32, Vec<u16>)>, MessageBinReadError> {
+        Ok(Self::load_raw(file)?
+            .into_iter()
+            .map(|message| (message.hash, message.unk, message.units))
+            .collect())
+    }
+
+    /// Load all the messages from the reader as [`RawMessage`], in the order they are stored in.
+    ///
+    /// The texts are neither decoded with a code table nor converted to [`String`], and entries sharing the same hash are all kept.
+    pub fn load_raw<T: Read + Seek>(file: &mut T) -> Result<Vec<RawMessage>, MessageBinReadError> {
+        let options = MessageBinReadOptions::default();
+        let strings_data = Self::read_strings_data(file, &options)?;
+
+        let mut result = Vec::with_capacity(strings_data.len());
+        for string_data in strings_data {
+            file.seek(SeekFrom::Start(string_data.string_pointer as u64))?;
+            let text: MessageBinText = file.read_type(options.text_endianness.binread())?;
+            result.push(RawMessage {
+                hash: string_data.string_hash,
+                unk: string_data.unk,
+                units: text.text.0,
+            });
+        }
+
+        Ok(result)
+    }
+
+    /// Check that the strings referenced by the metadata exactly cover the string block of the file, in order to spot
+    /// corruption or unusual layout. Anomalies are returned in the order of the file.
+    pub fn check_string_layout<T: Read + Seek>(
+        file: &mut T,
+        options: &MessageBinReadOptions,
+    ) -> Result<Vec<StringLayoutAnomaly>, MessageBinReadError> {
+        let sir0_header = Self::read_sir0_header(file, options.structure_endianness)?;
+        let strings_data = Self::read_strings_data_at(file, &sir0_header, options)?;
+        let block_end = sir0_header.string_info_pointer as u64;
 
+        let mut anomalies = Vec::new();
+        let mut current_end: u64 = 16;
+        // the strings overlapping the current position, with the end of each of them
+        let mut previous: Vec<(u32, u64)> = Vec::new();
+        let groups: Vec<&[MessageBinStringData]> = strings_data
+            .chunk_by(|a, b| a.string_pointer == b.string_pointer)
+            .collect();
+        for (index, group) in groups.iter().enumerate() {
+            let start = group[0].string_pointer as u64;
+            if group.len() > 1 {
+                anomalies.push(StringLayoutAnomaly::SharedOffset {
+                    offset: start,
+                    hashes: group.iter().map(|data| data.string_hash).collect(),
+                });
+            }
+
+            file.seek(SeekFrom::Start(start))?;
+            let text: MessageBinText = file.read_type(options.text_endianness.binread())?;
+            let mut end = start + (text.text.len() as u64 + 1) * 2;
+
+            // additional null terminators and per-string alignment are part of the string, up to the start of the next one
+            let next_start = groups
+                .get(index + 1)
+                .map_or(block_end, |next| next[0].string_pointer as u64);
+            for _ in 0..next_start.saturating_sub(end) / 2 {
+                let mut unit = [0; 2];
+                file.read_exact(&mut unit)?;
+                if unit != [0, 0] {
+                    break;
+                };
+                end += 2;
+            }
+
+            if start > current_end {
+                anomalies.push(StringLayoutAnomaly::Gap {
+                    start: current_end,
+                    length: start - current_end,
+                });
+            } else if start < current_end {
+                let mut hashes: Vec<u32> = previous
+                    .iter()
+                    .filter(|(_, previous_end)| *previous_end > start)
+                    .map(|(hash, _)| *hash)
+                    .collect();
+                hashes.push(group[0].string_hash);
+                anomalies.push(StringLayoutAnomaly::Overlap {
+                    start,
+                    length: current_end.min(end) - start,
+                    hashes,
+                });
+            }
+
+            previous.retain(|(_, previous_end)| *previous_end > start);
+            previous.push((group[0].string_hash, end));
+            current_end = current_end.max(end);
+        }
+
+        if current_end > block_end {
+            anomalies.push(StringLayoutAnomaly::Overlap {
+                start: block_end,
+                length: current_end - block_end,
+                hashes: previous
+                    .iter()
+                    .filter(|(_, previous_end)| *previous_end > block_end)
+                    .map(|(hash, _)| *hash)
+                    .collect(),
+            });
+        } else if current_end < block_end && (block_end - current_end >= 4 || block_end % 4 != 0) {
+            anomalies.push(StringLayoutAnomaly::Gap {
+                start: current_end,
+                length: block_end - current_end,
+            });
+        }
+
+        Ok(anomalies)
+    }
+
+    /// Return the offset and length of the string of every message, in the order of the strings in the file. Only the metadata are read.
+    ///
+    /// Messages sharing the same string have the same offset and length.
+    pub fn string_offsets<T: Read + Seek>(
+        file: &mut T,
+        options: &MessageBinReadOptions,
+    ) -> Result<Vec<StringOffset>, MessageBinReadError> {
+        let sir0_header = Self::read_sir0_header(file, options.structure_endianness)?;
+        let strings_data = Self::read_strings_data_at(file, &sir0_header, options)?;
+        let block_end = sir0_header.string_info_pointer as u64;
+        Ok(strings_data
+            .iter()
+            .enumerate()
+            .map(|(index, string_data)| {
+                let offset = string_data.string_pointer as u64;
+                let next_offset = strings_data[index + 1..]
+                    .iter()
+                    .map(|next| next.string_pointer as u64)
+                    .find(|next_offset| *next_offset > offset)
+                    .unwrap_or(block_end);
+                StringOffset {
+                    hash: string_data.string_hash,
+                    offset,
+                    length: next_offset.saturating_sub(offset),
+                }
+            })
+            .collect())
+    }
+
+    /// Split the whole file into labeled regions (Sir0 container, strings, metadata entries, header and padding), sorted
+    /// by their position. Useful to document or inspect the format.
+    pub fn layout_regions<T: Read + Seek>(
+        file: &mut T,
+        options: &MessageBinReadOptions,
+    ) -> Result<Vec<LayoutRegion>, MessageBinReadError> {
+        let endianness = options.structure_endianness;
+        let sir0_header = Self::read_sir0_header(file, endianness)?;
+        file.seek(SeekFrom::Start(4))?;
+        let header_position = endianness.read_u32(file)? as u64;
+        let footer_position = endianness.read_u32(file)? as u64;
+        let file_end = file.seek(SeekFrom::End(0))?;
+
+        let mut regions = vec![
+            LayoutRegion {
+                start: 0,
+                length: 16,
+                kind: LayoutRegionKind::Sir0Header,
+            },
+            LayoutRegion {
+                start: header_position,
+                length: 8,
+                kind: LayoutRegionKind::Header,
+            },
+            LayoutRegion {
+                start: footer_position,
+                length: file_end.saturating_sub(footer_position),
+                kind: LayoutRegionKind::Sir0Footer,
+            },
+        ];
+
+        file.seek(SeekFrom::Start(sir0_header.string_info_pointer as u64))?;
         let mut strings_data: Vec<MessageBinStringData> =
             Vec::with_capacity(sir0_header.string_count as usize);
+        for index in 0..sir0_header.string_count as u64 {
+            let string_data: MessageBinStringData = file.read_type(endianness.binread())?;
+            regions.push(LayoutRegion {
+                start: sir0_header.string_info_pointer as u64 + index * 12,
+                length: 12,
+                kind: LayoutRegionKind::StringMetadata {
+                    hash: string_data.string_hash,
+                },
+            });
+            strings_data.push(string_data);
+        }
+
+        strings_data.sort_by_key(|e| e.string_pointer);
+        for group in strings_data.chunk_by(|a, b| a.string_pointer == b.string_pointer) {
+            let start = group[0].string_pointer as u64;
+            file.seek(SeekFrom::Start(start))?;
+            let text: MessageBinText = file.read_type(options.text_endianness.binread())?;
+            regions.push(LayoutRegion {
+                start,
+                length: (text.text.len() as u64 + 1) * 2,
+                kind: LayoutRegionKind::String {
+                    hashes: group.iter().map(|data| data.string_hash).collect(),
+                },
+            });
+        }
+
+        regions.sort_by_key(|region| region.start);
+        let mut result = Vec::with_capacity(regions.len());
+        let mut current_end = 0;
+        for region in regions {
+            if region.start > current_end {
+                result.push(LayoutRegion {
+                    start: current_end,
+                    length: region.start - current_end,
+                    kind: LayoutRegionKind::Padding,
+                });
+            };
+            current_end = current_end.max(region.start + region.length);
+            result.push(region);
+        }
+        if file_end > current_end {
+            result.push(LayoutRegion {
+                start: current_end,
+                length: file_end - current_end,
+                kind: LayoutRegionKind::Padding,
+            });
+        };
+
+        Ok(result)
+    }
+
+    /// Return true if the metadata entries are stored in ascending hash order (as [`MessageBin::write`] does), which
+    /// a binary search of a hash in the file rely on. Only the Sir0 container, the header and the metadata are read.
+    pub fn verify_metadata_sorted<T: Read + Seek>(
+        file: &mut T,
+    ) -> Result<bool, MessageBinReadError> {
+        let endianness = MessageBinReadOptions::default().structure_endianness;
+        let sir0_header = Self::read_sir0_header(file, endianness)?;
+        file.seek(SeekFrom::Start(sir0_header.string_info_pointer as u64))?;
+        let mut previous_hash = None;
         for _ in 0..sir0_header.string_count {
-            strings_data.push(file.read_le()?);
+            let string_data: MessageBinStringData = file.read_type(endianness.binread())?;
+            if previous_hash.is_some_and(|previous| previous > string_data.string_hash) {
+                return Ok(false);
+            };
+            previous_hash = Some(string_data.string_hash);
+        }
+        Ok(true)
+    }
+
+    /// Read the metadata of all the strings in the file, sorted by the position of the string they point to.
+    fn read_strings_data<T: Read + Seek>(
+        file: &mut T,
+        options: &MessageBinReadOptions,
+    ) -> Result<Vec<MessageBinStringData>, MessageBinReadError> {
+        let sir0_header = Self::read_sir0_header(file, options.structure_endianness)?;
+        Self::read_strings_data_at(file, &sir0_header, options)
+    }
+
+    /// Read the metadata of all the strings pointed by the given header, sorted by the position of the string they point to.
+    fn read_strings_data_at<T: Read + Seek>(
+        file: &mut T,
+        sir0_header: &MessageBinSir0Header,
+        options: &MessageBinReadOptions,
+    ) -> Result<Vec<MessageBinStringData>, MessageBinReadError> {
+        if let Some(limit) = options.max_strings {
+            if sir0_header.string_count as u64 > limit {
+                return Err(MessageBinReadError::BudgetExceeded);
+            };
+        };
+        if let Some(limit) = options.max_bytes_read {
+            if sir0_header.string_count as u64 * 12 > limit {
+                return Err(MessageBinReadError::BudgetExceeded);
+            };
+        };
+        file.seek(SeekFrom::Start(sir0_header.string_info_pointer as u64))?;
+
+        let structure_endian = options.structure_endianness.binread();
+        // the number of strings isn't trusted, so the vector grow as the entries are actually read
+        let mut strings_data: Vec<MessageBinStringData> = Vec::new();
+        for _ in 0..sir0_header.string_count {
+            strings_data.push(file.read_type(structure_endian)?);
         }
 
         strings_data.sort_unstable_by_key(|e| e.string_pointer);
+        trace_event!(debug, count = strings_data.len(), "string metadata read");
 
-        let mut message_bin = MessageBin::default();
-        for string_data in strings_data {
-            file.seek(SeekFrom::Start(string_data.string_pointer as u64))?;
-            let text: MessageBinText = file.read_le()?;
-            let text = if let Some(code_to_text) = code_to_text {
-                code_to_text.decode(&text.text).map_err(|err| {
-                    MessageBinReadError::CantDecodeString(err, text.text.to_string())
-                })?
-            } else {
-                text.text.to_string()
+        Ok(strings_data)
+    }
+
+    /// Read the Sir0 container of the file, and return the message header it point to.
+    fn read_sir0_header<T: Read + Seek>(
+        mut file: &mut T,
+        endianness: Endianness,
+    ) -> Result<MessageBinSir0Header, MessageBinReadError> {
+        file.seek(SeekFrom::Start(0))?;
+        let mut read_magic = Vec::with_capacity(4);
+        file.by_ref().take(4).read_to_end(&mut read_magic)?;
+        if read_magic != *b"SIR0" {
+            let mut found = [0; 4];
+            found[..read_magic.len()].copy_from_slice(&read_magic);
+            return Err(MessageBinReadError::NotSir0 { found });
+        };
+        let sir0_header: MessageBinSir0Header = match endianness {
+            Endianness::Little => {
+                file.seek(SeekFrom::Start(0))?;
+                let sir0 = Sir0::new(&mut file)?;
+                let mut sir0_header_cursor = Cursor::new(sir0.get_header());
+                sir0_header_cursor.read_type(endianness.binread())?
+            }
+            // pmd_sir0 only read little endian container, so only the part used here are read manually
+            Endianness::Big => {
+                let header_offset = endianness.read_u32(file)?;
+                file.seek(SeekFrom::Start(header_offset as u64))?;
+                file.read_type(endianness.binread())?
+            }
+        };
+        trace_event!(
+            debug,
+            string_count = sir0_header.string_count,
+            string_info_pointer = sir0_header.string_info_pointer,
+            "sir0 container parsed"
+        );
+        Ok(sir0_header)
+    }
+
+    /// Return the exact size, in bytes, of the file [`MessageBin::write`] would produce.
+    pub fn estimated_size(
+        &self,
+        text_to_code: Option<&dyn Encoder>,
+    ) -> Result<u64, MessageBinWriteError> {
+        self.estimated_size_with_options(text_to_code, &MessageBinWriteOptions::default())
+    }
+
+    /// Return the exact size, in bytes, of the file [`MessageBin::write_with_options`] would produce with these options.
+    pub fn estimated_size_with_options(
+        &self,
+        text_to_code: Option<&dyn Encoder>,
+        options: &MessageBinWriteOptions,
+    ) -> Result<u64, MessageBinWriteError> {
+        writer::check_string_alignment(options.string_alignment)?;
+        let alignment = options.string_alignment.max(1) as u64;
+        let mut string_block_len: u64 = 0;
+        for (hash, _, text) in self.message.iter() {
+            // the string block start at 16, which is a multiple of every valid alignment
+            string_block_len += self.encoded_len(*hash, text, text_to_code)?;
+            string_block_len = string_block_len.div_ceil(alignment) * alignment;
+        }
+        writer::written_file_size(string_block_len, self.message.len() as u64)
+    }
+
+    /// Return the size, in bytes, the given message take in the string block (including its terminator and trailing nulls).
+    fn encoded_len(
+        &self,
+        hash: u32,
+        text: &str,
+        text_to_code: Option<&dyn Encoder>,
+    ) -> Result<u64, MessageBinWriteError> {
+        let encoded_units = writer::encode_text(hash, text, text_to_code)?.len() as u64
+            + 1
+            + self.trailing_nulls(hash) as u64;
+        Ok(encoded_units * 2)
+    }
+
+    /// Return by how many bytes the string of the message with the given hash would grow (or shrink, if negative) if its text was replaced by `new_text`.
+    ///
+    /// The size include the terminator and trailing nulls, but not the alignment padding. If there is no message with this hash,
+    /// return the size of the string a new message would take.
+    pub fn size_delta_for_edit(
+        &self,
+        hash: u32,
+        new_text: &str,
+        text_to_code: Option<&dyn Encoder>,
+    ) -> Result<i64, MessageBinWriteError> {
+        let old_len: i64 = match self.message_by_hash(hash) {
+            Some(text) => self.encoded_len(hash, text, text_to_code)?.try_into()?,
+            None => 0,
+        };
+        let new_len: i64 = self.encoded_len(hash, new_text, text_to_code)?.try_into()?;
+        Ok(new_len - old_len)
+    }
+
+    /// Return the hash and encoded size (in bytes, as counted by [`MessageBin::estimated_size`]) of the `n` biggest messages,
+    /// from the biggest to the smallest. Messages of the same size are kept in the order of the file.
+    pub fn largest_messages(
+        &self,
+        text_to_code: Option<&dyn Encoder>,
+        n: usize,
+    ) -> Result<Vec<(u32, usize)>, MessageBinWriteError> {
+        let mut sizes = Vec::with_capacity(self.message.len());
+        for (hash, _, text) in self.message.iter() {
+            sizes.push((
+                *hash,
+                self.encoded_len(*hash, text, text_to_code)?.try_into()?,
+            ));
+        }
+        sizes.sort_by_key(|(_, size): &(u32, usize)| std::cmp::Reverse(*size));
+        sizes.truncate(n);
+        Ok(sizes)
+    }
+
+    /// Return true if the file [`MessageBin::write`] would produce is no bigger than `budget` bytes.
+    pub fn fits_in(
+        &self,
+        text_to_code: Option<&dyn Encoder>,
+        budget: u64,
+    ) -> Result<bool, MessageBinWriteError> {
+        Ok(self.estimated_size(text_to_code)? <= budget)
+    }
+
+    /// Return by how many bytes the file [`MessageBin::write`] would produce exceed `budget`. A negative value is the remaining free space.
+    pub fn overflow_amount(
+        &self,
+        text_to_code: Option<&dyn Encoder>,
+        budget: u64,
+    ) -> Result<i64, MessageBinWriteError> {
+        let size: i64 = self.estimated_size(text_to_code)?.try_into()?;
+        let budget: i64 = budget.try_into()?;
+        Ok(size - budget)
+    }
+
+    /// Return the size, in bytes, the file would have if messages with identical encoded text shared the same string.
+    ///
+    /// Compare with [`MessageBin::estimated_size`] to know how much space such a deduplication would save.
+    pub fn min_size_with_dedup(
+        &self,
+        text_to_code: Option<&dyn Encoder>,
+    ) -> Result<u64, MessageBinWriteError> {
+        let mut encoded_texts = HashSet::new();
+        let mut string_block_len: u64 = 0;
+        for (hash, _, text) in self.message.iter() {
+            let encoded = writer::encode_text(*hash, text, text_to_code)?;
+            let encoded_len = (encoded.len() as u64 + 1 + self.trailing_nulls(*hash) as u64) * 2;
+            if encoded_texts.insert(encoded) {
+                string_block_len += encoded_len;
+            }
+        }
+        writer::written_file_size(string_block_len, self.message.len() as u64)
+    }
+
+    /// Return the hashes of the messages whose encoded form differ between the `old` and `new` code tables, in the order of the file.
+    ///
+    /// On error, [`MessageBinWriteError::CantEncodeWithTable`] tell which message failed with which table (`"old"` or `"new"`).
+    pub fn table_migration_diff(
+        &self,
+        old: &dyn Encoder,
+        new: &dyn Encoder,
+    ) -> Result<Vec<u32>, MessageBinWriteError> {
+        let mut result = Vec::new();
+        for (hash, _, text) in self.message.iter() {
+            let encode = |encoder: &dyn Encoder, table| {
+                encoder
+                    .encode(text)
+                    .map_err(|source| MessageBinWriteError::CantEncodeWithTable {
+                        hash: *hash,
+                        table,
+                        source,
+                    })
             };
-            message_bin.insert(string_data.string_hash, string_data.unk, text);
+            if encode(old, "old")? != encode(new, "new")? {
+                result.push(*hash);
+            }
         }
+        Ok(result)
+    }
 
-        Ok(message_bin)
+    /// Load the file, write it back in memory, and compare the result with the original bytes.
+    pub fn verify_roundtrip_bytes<T: Read + Seek>(
+        original: &mut T,
+        text_to_code: Option<&dyn Encoder>,
+        code_to_text: Option<&dyn Decoder>,
+    ) -> Result<RoundtripReport, RoundtripError> {
+        let mut original_bytes = Vec::new();
+        original.seek(SeekFrom::Start(0))?;
+        original.read_to_end(&mut original_bytes)?;
+
+        let message = Self::load_file(&mut Cursor::new(&original_bytes), code_to_text)?;
+        let mut rewritten = Cursor::new(Vec::new());
+        message.write(&mut rewritten, text_to_code)?;
+
+        Ok(RoundtripReport::compare(
+            &original_bytes,
+            &rewritten.into_inner(),
+        ))
+    }
+
+    /// Write the messages, load them back and check they are unchanged, then check that writing the loaded messages again
+    /// produce the same bytes. Panic with a description of the difference otherwise.
+    ///
+    /// This is intended for the tests of crates using this one, to check their messages and code tables round-trip.
+    /// The messages are compared by hash. Trailing nulls are only compared through the bytes, as those at the end of the
+    /// string block may be merged with its padding.
+    ///
+    /// ```
+    /// use pmd_message::MessageBin;
+    ///
+    /// let message = MessageBin::from_messages(vec![(0x1234, 1, "Hello".to_string())]);
+    /// message.assert_roundtrip(None, None);
+    /// ```
+    pub fn assert_roundtrip(
+        &self,
+        text_to_code: Option<&dyn Encoder>,
+        code_to_text: Option<&dyn Decoder>,
+    ) {
+        let written = self
+            .to_bytes(text_to_code)
+            .expect("can't write the messages");
+        let reloaded =
+            Self::from_bytes(&written, code_to_text).expect("can't load the written file");
+        let by_hash = |message: &MessageBin| {
+            message
+                .iter()
+                .map(|(hash, unk, text)| (hash, (unk, text.to_string())))
+                .collect::<BTreeMap<u32, (u32, String)>>()
+        };
+        assert_eq!(
+            by_hash(self),
+            by_hash(&reloaded),
+            "the loaded messages differ from the written ones"
+        );
+        let rewritten = reloaded
+            .to_bytes(text_to_code)
+            .expect("can't write the loaded messages back");
+        let report = RoundtripReport::compare(&written, &rewritten);
+        assert!(
+            report.is_identical(),
+            "writing the loaded messages back produce different bytes: {:?}",
+            report
+        );
+    }
+
+    /// Write the messages in a human readable, code table free, format for debugging. The texts are stored as UTF-8.
+    ///
+    /// The file starts with [`DEBUG_UTF8_MAGIC`] instead of a Sir0 container, so it can't be mistaken for a game file.
+    /// Trailing nulls aren't kept. Use [`MessageBin::load_debug_utf8`] to read it back.
+    pub fn write_debug_utf8<T: Write>(&self, file: &mut T) -> Result<(), MessageBinWriteError> {
+        debug_utf8::write_debug_utf8(self, file)
+    }
+
+    /// Load a file written by [`MessageBin::write_debug_utf8`]. Fail with [`MessageBinReadError::InvalidDebugMagic`] on any other file.
+    pub fn load_debug_utf8<T: Read>(file: &mut T) -> Result<Self, MessageBinReadError> {
+        debug_utf8::load_debug_utf8(file)
+    }
+
+    /// Write a MessageBin in memory, and return the content of the file.
+    ///
+    /// ```
+    /// use pmd_message::MessageBin;
+    ///
+    /// let message = MessageBin::from_messages(vec![(0x1234, 1, "Hello".to_string())]);
+    /// let bytes = message.to_bytes(None).unwrap();
+    /// let reloaded = MessageBin::from_bytes(&bytes, None).unwrap();
+    /// assert_eq!(reloaded.messages(), message.messages());
+    /// ```
+    pub fn to_bytes(
+        &self,
+        text_to_code: Option<&dyn Encoder>,
+    ) -> Result<Vec<u8>, MessageBinWriteError> {
+        let mut file = Cursor::new(Vec::new());
+        self.write(&mut file, text_to_code)?;
+        Ok(file.into_inner())
     }
 
-    // Write a MessageBin to the given writer.
-    //TODO: ugly, rewrite & cleanup
+    /// Write a MessageBin to the given writer.
     pub fn write<T: Seek + Write>(
         &self,
         file: &mut T,
-        text_to_code: Option<&TextToCode>,
+        text_to_code: Option<&dyn Encoder>,
     ) -> Result<(), MessageBinWriteError> {
-        let mut sir0_offsets: Vec<u32> = vec![4, 8];
+        self.write_with_options(file, text_to_code, &MessageBinWriteOptions::default())
+    }
 
-        file.write_all(&[0; 16])?; //sir0 header and padding
+    /// Write a MessageBin to the given writer, and return the length of the written file, in bytes.
+    ///
+    /// ```
+    /// use pmd_message::MessageBin;
+    /// use std::io::Cursor;
+    ///
+    /// let message = MessageBin::from_messages(vec![(0x1234, 1, "Hello".to_string())]);
+    /// let mut file = Cursor::new(Vec::new());
+    /// let len = message.write_and_len(&mut file, None).unwrap();
+    /// assert_eq!(len, file.get_ref().len() as u64);
+    /// ```
+    pub fn write_and_len<T: Seek + Write>(
+        &self,
+        file: &mut T,
+        text_to_code: Option<&dyn Encoder>,
+    ) -> Result<u64, MessageBinWriteError> {
+        self.write_with_options_and_len(file, text_to_code, &MessageBinWriteOptions::default())
+    }
 
-        let mut strings_data = Vec::new();
-        let mut text_current_offset: u32 = 16;
-        for (hash, unk, text) in self.messages().iter() {
-            let text_to_write = if let Some(text_to_code) = text_to_code {
-                text_to_code
-                    .encode(text)
-                    .map_err(|err| MessageBinWriteError::CantEncodeText(err, text.to_string()))?
+    /// Write a MessageBin to the given writer, using the given [`MessageBinWriteOptions`].
+    pub fn write_with_options<T: Seek + Write>(
+        &self,
+        file: &mut T,
+        text_to_code: Option<&dyn Encoder>,
+        options: &MessageBinWriteOptions,
+    ) -> Result<(), MessageBinWriteError> {
+        self.write_with_options_and_len(file, text_to_code, options)
+            .map(|_| ())
+    }
+
+    /// Write a MessageBin to the given writer, using the given [`MessageBinWriteOptions`], and return the length of the written file, in bytes.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn write_with_options_and_len<T: Seek + Write>(
+        &self,
+        file: &mut T,
+        text_to_code: Option<&dyn Encoder>,
+        options: &MessageBinWriteOptions,
+    ) -> Result<u64, MessageBinWriteError> {
+        if let Some(expected) = self.text_mode {
+            let found = if text_to_code.is_some() {
+                TextMode::Decoded
             } else {
-                text.encode_utf16().collect()
+                TextMode::Raw
             };
-            let mut binary_text_to_write = text_to_write
-                .iter()
-                .flat_map(|v| v.to_le_bytes().to_vec())
-                .collect::<Vec<u8>>();
-            binary_text_to_write.push(0);
-            binary_text_to_write.push(0);
-            file.write_all(&binary_text_to_write)?;
-            strings_data.push(MessageBinStringData {
-                string_pointer: text_current_offset,
-                string_hash: *hash,
-                unk: *unk,
-            });
+            if expected != found {
+                return Err(MessageBinWriteError::TextModeMismatch { expected, found });
+            };
+        };
+        let mut writer = MessageBinWriter::new(file, text_to_code, options.clone())?;
+        for (hash, unk, text) in self.messages().iter() {
+            writer.push_with_trailing_nulls(*hash, *unk, text, self.trailing_nulls(*hash))?;
+        }
+        writer.finish_and_len()
+    }
+}
+
+impl IntoIterator for MessageBin {
+    type Item = (u32, u32, String);
+    type IntoIter = std::vec::IntoIter<(u32, u32, String)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.message.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a MessageBin {
+    type Item = (u32, u32, &'a str);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
 
-            text_current_offset = text_current_offset
-                .checked_add(binary_text_to_write.len().try_into()?)
-                .map_or_else(|| Err(MessageBinWriteError::Overflow), Ok)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Return the file written for `message`, with the number of strings of its header replaced
+    fn with_string_count(message: &MessageBin, string_count: u32) -> Vec<u8> {
+        let mut bytes = message.to_bytes(None).unwrap();
+        let header_offset = MessageBin::from_bytes(&bytes, None)
+            .unwrap()
+            .file_info()
+            .unwrap()
+            .header_offset as usize;
+        bytes[header_offset..header_offset + 4].copy_from_slice(&string_count.to_le_bytes());
+        bytes
+    }
+
+    /// Build a little endian file with the given string block and (pointer, hash, unk) metadata entries, without any padding
+    fn build_file(string_block: &[u8], metadata: &[(u32, u32, u32)]) -> Vec<u8> {
+        let mut bytes = vec![0; 16];
+        bytes.extend_from_slice(string_block);
+        let meta_position = bytes.len() as u32;
+        let mut sir0_offsets = vec![4, 8];
+        for (count, (pointer, hash, unk)) in metadata.iter().enumerate() {
+            sir0_offsets.push(meta_position + count as u32 * 12);
+            for value in [pointer, hash, unk] {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
         }
+        let header_position = bytes.len() as u32;
+        sir0_offsets.push(header_position + 4);
+        bytes.extend_from_slice(&(metadata.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&meta_position.to_le_bytes());
+        let footer_position = bytes.len() as u32;
+        pmd_sir0::write_sir0_footer(&mut bytes, &sir0_offsets).unwrap();
+        bytes[0..4].copy_from_slice(b"SIR0");
+        bytes[4..8].copy_from_slice(&header_position.to_le_bytes());
+        bytes[8..12].copy_from_slice(&footer_position.to_le_bytes());
+        bytes
+    }
 
-        // padding of 4
-        #[allow(unused_assignments)]
-        if text_current_offset % 4 != 0 {
-            let nb_to_seek = 4 - text_current_offset % 4;
-            file.write_all(&vec![0; nb_to_seek as usize])?;
-            text_current_offset += nb_to_seek;
+    fn layout_anomalies(bytes: &[u8]) -> Vec<StringLayoutAnomaly> {
+        MessageBin::check_string_layout(&mut Cursor::new(bytes), &Default::default()).unwrap()
+    }
+
+    #[test]
+    fn string_layout_unaligned_end_without_padding() {
+        // "ab" end at 22, where the metadata start
+        let bytes = build_file(&[b'a', 0, b'b', 0, 0, 0], &[(16, 1, 0)]);
+        assert_eq!(layout_anomalies(&bytes), Vec::new());
+    }
+
+    #[test]
+    fn string_layout_gap() {
+        let bytes = build_file(
+            &[b'a', 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, b'b', 0, 0, 0],
+            &[(16, 1, 0), (24, 2, 0)],
+        );
+        assert_eq!(
+            layout_anomalies(&bytes),
+            vec![StringLayoutAnomaly::Gap {
+                start: 20,
+                length: 4
+            }]
+        );
+    }
+
+    #[test]
+    fn string_layout_trailing_nulls() {
+        let mut message =
+            MessageBin::from_messages(vec![(1, 0, "a".to_string()), (2, 0, "b".to_string())]);
+        message.set_trailing_nulls(1, 1);
+        message.set_trailing_nulls(2, 3);
+        let bytes = message.to_bytes(None).unwrap();
+        assert_eq!(layout_anomalies(&bytes), Vec::new());
+    }
+
+    #[test]
+    fn string_layout_overlap() {
+        let bytes = build_file(
+            &[b'a', 0, b'b', 0, b'c', 0, 0, 0],
+            &[(16, 1, 0), (18, 2, 0)],
+        );
+        assert_eq!(
+            layout_anomalies(&bytes),
+            vec![StringLayoutAnomaly::Overlap {
+                start: 18,
+                length: 6,
+                hashes: vec![1, 2]
+            }]
+        );
+    }
+
+    /// A small xorshift pseudo-random generator, so the generated files are the same on each run
+    struct XorShift(u32);
+
+    impl XorShift {
+        fn next(&mut self) -> u32 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 17;
+            self.0 ^= self.0 << 5;
+            self.0
+        }
+    }
+
+    #[test]
+    fn random_messages_roundtrip() {
+        let mut random = XorShift(0x2545_F491);
+        let chars = [
+            'a', 'Z', 'é', ' ', '\n', '[', ']', '\u{E000}', '\u{FFFF}', '😀',
+        ];
+        for _ in 0..500 {
+            let mut message = MessageBin::default();
+            for _ in 0..random.next() % 16 {
+                let text: String = (0..random.next() % 24)
+                    .map(|_| chars[random.next() as usize % chars.len()])
+                    .collect();
+                // few different hashes, so some are replaced
+                let hash = random.next() % 32;
+                message.insert(hash, random.next(), text);
+                message.set_trailing_nulls(hash, (random.next() % 3) as u16);
+            }
+            message.assert_roundtrip(None, None);
+        }
+    }
+
+    /// Encode the texts as UTF-16, like when no code table is used
+    struct Utf16Encoder;
+
+    impl Encoder for Utf16Encoder {
+        fn encode(&self, text: &str) -> Result<Vec<u16>, CodingError> {
+            Ok(text.encode_utf16().collect())
         }
+    }
 
-        strings_data.sort_unstable_by_key(|e| e.string_hash);
+    /// Decode the texts as UTF-16, but fail on the text "bad"
+    struct FailingDecoder;
 
-        let string_meta_position: u32 = file.seek(SeekFrom::Current(0))?.try_into()?;
-        strings_data.write(file)?; // * macro magic * !!!
-        for count in 0..strings_data.len() {
-            sir0_offsets.push(string_meta_position + (count as u32) * 12);
+    impl Decoder for FailingDecoder {
+        fn decode(&self, units: &[u16]) -> Result<String, CodingError> {
+            let text = String::from_utf16_lossy(units);
+            if text == "bad" {
+                return Err("can't decode".into());
+            };
+            Ok(text)
         }
+    }
+
+    #[test]
+    fn load_file_filtered_skip_other_strings() {
+        let bytes = MessageBin::from_messages(vec![
+            (0x10, 1, "kept".to_string()),
+            (0x20, 2, "bad".to_string()),
+            (0x30, 3, "also kept".to_string()),
+            (0x40, 4, "skipped".to_string()),
+        ])
+        .to_bytes(None)
+        .unwrap();
+        assert!(MessageBin::from_bytes(&bytes, Some(&FailingDecoder)).is_err());
 
-        let number_of_strings: u32 = strings_data.len().try_into()?;
-        let string_relative_end_offset = number_of_strings
-            .checked_mul(12)
-            .map_or_else(|| Err(MessageBinWriteError::Overflow), Ok)?;
-        let string_absolute_end_offset = string_meta_position
-            .checked_add(string_relative_end_offset)
-            .map_or_else(|| Err(MessageBinWriteError::Overflow), Ok)?;
-        sir0_offsets.push(
-            string_absolute_end_offset
-                .checked_add(4)
-                .map_or_else(|| Err(MessageBinWriteError::Overflow), Ok)?,
+        // the string that can't be decoded isn't in the filter, so it is never decoded
+        let filter: HashSet<u32> = [0x10, 0x30, 0x50].iter().copied().collect();
+        let filtered = MessageBin::load_file_filtered(
+            &mut Cursor::new(&bytes),
+            Some(&FailingDecoder),
+            &filter,
+        )
+        .unwrap();
+        assert_eq!(
+            filtered.messages(),
+            &vec![
+                (0x10, 1, "kept".to_string()),
+                (0x30, 3, "also kept".to_string())
+            ]
         );
+    }
 
-        let sir0_header_position = file.seek(SeekFrom::Current(0))?;
-        file.write_u32::<LE>(strings_data.len().try_into()?)?;
-        file.write_u32::<LE>(string_meta_position)?;
+    #[test]
+    fn find_by_hash_prefix_match_top_bits() {
+        let message = MessageBin::from_messages(vec![
+            (0xABCD_0001, 0, String::new()),
+            (0x1234_5678, 0, String::new()),
+            (0xABFF_FFFF, 0, String::new()),
+            (0xAB00_0000, 0, String::new()),
+            (0xAC00_0000, 0, String::new()),
+        ]);
+        assert_eq!(
+            message.find_by_hash_prefix(0xAB, 8),
+            vec![0xAB00_0000, 0xABCD_0001, 0xABFF_FFFF]
+        );
+        assert_eq!(message.find_by_hash_prefix(0xABCD, 16), vec![0xABCD_0001]);
+        // the prefix don't need to be a whole number of hexadecimal digits: 0b1 match the hashes starting with a bit set
+        assert_eq!(
+            message.find_by_hash_prefix(1, 1),
+            vec![0xAB00_0000, 0xABCD_0001, 0xABFF_FFFF, 0xAC00_0000]
+        );
+        assert_eq!(message.find_by_hash_prefix(0, 0).len(), 5);
+        assert_eq!(
+            message.find_by_hash_prefix(0x1234_5678, 32),
+            vec![0x1234_5678]
+        );
+        assert_eq!(
+            message.find_by_hash_prefix(0x1234_5678, 40),
+            vec![0x1234_5678]
+        );
+        assert_eq!(message.find_by_hash_prefix(0xEE, 8), Vec::<u32>::new());
+        // a prefix with more bits than prefix_bits can't match anything
+        assert_eq!(message.find_by_hash_prefix(0x1AB, 8), Vec::<u32>::new());
+    }
 
-        let current_position = file.seek(SeekFrom::Current(0))?;
-        //TODO: this might need some magic :)
-        if current_position % 16 != 0 {
-            file.write_all(&vec![0; 16 - (current_position as usize % 16)])?;
-        };
+    #[test]
+    fn set_line_break_mode_keep_other_bits() {
+        let mut message = MessageBin::from_messages(vec![(1, 0xABCD_0001, "a".to_string())]);
+        assert_eq!(message.line_break_mode(1), Some(LineBreakMode::Manual));
+        assert!(message.set_line_break_mode(1, LineBreakMode::NoWrap));
+        assert_eq!(message.unk_by_hash(1), Some(0xABCD_0002));
+        assert_eq!(message.line_break_mode(1), Some(LineBreakMode::NoWrap));
+        assert!(message.set_line_break_mode(1, LineBreakMode::Auto));
+        assert_eq!(message.unk_by_hash(1), Some(0xABCD_0000));
+
+        assert!(!message.set_line_break_mode(2, LineBreakMode::Auto));
+        assert_eq!(message.line_break_mode(2), None);
+        assert_eq!(message.len(), 1);
+    }
+
+    #[test]
+    fn group_by_unk_keep_relative_order() {
+        let message = MessageBin::from_messages(vec![
+            (0x40, 2, "d".to_string()),
+            (0x10, 1, "a".to_string()),
+            (0x30, 2, "c".to_string()),
+            (0x20, 1, "b".to_string()),
+        ]);
+        let groups = message.group_by_unk();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(
+            groups[&1].messages(),
+            &vec![(0x10, 1, "a".to_string()), (0x20, 1, "b".to_string())]
+        );
+        assert_eq!(
+            groups[&2].messages(),
+            &vec![(0x40, 2, "d".to_string()), (0x30, 2, "c".to_string())]
+        );
+        assert!(MessageBin::default().group_by_unk().is_empty());
+    }
 
-        let sir0_footer_position = file.seek(SeekFrom::Current(0))?;
+    #[test]
+    fn export_subset_map_ignore_missing_hashes() {
+        let message = MessageBin::from_messages(vec![
+            (0x30, 0, "c".to_string()),
+            (0x10, 0, "a".to_string()),
+            (0x20, 0, "b".to_string()),
+        ]);
+        let subset = message.export_subset_map(&[0x30, 0x99, 0x10]);
+        assert_eq!(
+            subset.into_iter().collect::<Vec<_>>(),
+            vec![(0x10, "a".to_string()), (0x30, "c".to_string())]
+        );
+        assert!(message.export_subset_map(&[]).is_empty());
+    }
 
-        write_sir0_footer(file, &sir0_offsets)?;
+    #[test]
+    fn size_delta_for_edit_count_code_units() {
+        let mut message = MessageBin::from_messages(vec![(1, 0, "abc".to_string())]);
+        assert_eq!(message.size_delta_for_edit(1, "abcde", None).unwrap(), 4);
+        assert_eq!(message.size_delta_for_edit(1, "a", None).unwrap(), -4);
+        assert_eq!(message.size_delta_for_edit(1, "xyz", None).unwrap(), 0);
+        // a character outside of the basic multilingual plane take two code units
+        assert_eq!(
+            message.size_delta_for_edit(1, "ab\u{1F600}", None).unwrap(),
+            2
+        );
+        // a new message take its terminator and trailing nulls in addition to its text
+        assert_eq!(message.size_delta_for_edit(2, "ab", None).unwrap(), 6);
+        message.set_trailing_nulls(2, 1);
+        assert_eq!(message.size_delta_for_edit(2, "ab", None).unwrap(), 8);
 
-        /*if current_position % 16 != 0 {
-            file.write_all(&vec![0; 16 - (current_position as usize % 16)])?;
-        };*/
+        // the delta is the difference of the written file sizes, when it keeps every padding the same, like with 16 bytes
+        let original = MessageBin::from_messages(vec![(1, 0, "abc".to_string())]);
+        let edited = MessageBin::from_messages(vec![(1, 0, "abcdefghijk".to_string())]);
+        assert_eq!(
+            edited.estimated_size(None).unwrap() - original.estimated_size(None).unwrap(),
+            original
+                .size_delta_for_edit(1, "abcdefghijk", None)
+                .unwrap() as u64
+        );
+    }
 
-        //file.write_all(&[0; 11])?; //TODO: this doesn't look like a padding ...
+    #[test]
+    fn group_by_unk_keep_text_mode_and_trailing_nulls() {
+        let mut message = MessageBin::from_messages(vec![
+            (1, 5, "a".to_string()),
+            (2, 6, "b".to_string()),
+            (3, 5, "c".to_string()),
+        ]);
+        message.set_trailing_nulls(1, 2);
+        let raw = MessageBin::from_bytes(&message.to_bytes(None).unwrap(), None).unwrap();
+        assert_eq!(raw.text_mode(), Some(TextMode::Raw));
 
-        file.seek(SeekFrom::Start(0))?;
-        write_sir0_header(
-            file,
-            sir0_header_position.try_into()?,
-            sir0_footer_position.try_into()?,
-        )?;
-        Ok(())
+        let groups = raw.group_by_unk();
+        assert_eq!(groups.keys().copied().collect::<Vec<_>>(), vec![5, 6]);
+        assert_eq!(groups[&5].keys().collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(groups[&5].trailing_nulls(1), 2);
+        for group in groups.values() {
+            assert_eq!(group.text_mode(), Some(TextMode::Raw));
+            assert!(matches!(
+                group.write(&mut Cursor::new(Vec::new()), Some(&Utf16Encoder)),
+                Err(MessageBinWriteError::TextModeMismatch {
+                    expected: TextMode::Raw,
+                    found: TextMode::Decoded
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn estimated_size_equal_written_size() {
+        let mut message = MessageBin::from_messages(vec![
+            (0x30, 0, "Hello".to_string()),
+            (0x10, 0, "a".to_string()),
+            (0x20, 0, "".to_string()),
+        ]);
+        message.set_trailing_nulls(0x10, 1);
+        for (padding_byte, string_alignment) in [(0, 0), (0xFF, 0), (0, 4), (0xFF, 8), (0, 16)] {
+            let options = MessageBinWriteOptions {
+                padding_byte,
+                string_alignment,
+                ..Default::default()
+            };
+            let mut file = Cursor::new(Vec::new());
+            let len = message
+                .write_with_options_and_len(&mut file, None, &options)
+                .unwrap();
+            assert_eq!(len, file.get_ref().len() as u64);
+            assert_eq!(
+                message.estimated_size_with_options(None, &options).unwrap(),
+                len,
+                "padding byte {}, alignment {}",
+                padding_byte,
+                string_alignment
+            );
+        }
+
+        let size = message.to_bytes(None).unwrap().len() as u64;
+        assert_eq!(message.estimated_size(None).unwrap(), size);
+        assert!(message.fits_in(None, size).unwrap());
+        assert!(!message.fits_in(None, size - 1).unwrap());
+        assert_eq!(message.overflow_amount(None, size - 4).unwrap(), 4);
+        assert_eq!(message.overflow_amount(None, size + 4).unwrap(), -4);
+    }
+
+    #[test]
+    fn lint_line_count_with_line_break_placeholder() {
+        let message = MessageBin::from_messages(vec![
+            (1, 0, "one[LB]two[LB]three".to_string()),
+            (2, 0, "one\ntwo[LB]three".to_string()),
+            (3, 0, "one[CS:A]two\\[LB]".to_string()),
+            (4, 0, "one\ntwo".to_string()),
+        ]);
+        assert_eq!(message.lint_line_count(2, "[LB]"), vec![(1, 3), (2, 3)]);
+        assert_eq!(
+            message.lint_line_count(1, "[LB]"),
+            vec![(1, 3), (2, 3), (4, 2)]
+        );
+        // without the placeholder, only the \n are counted
+        assert_eq!(message.lint_line_count(1, ""), vec![(2, 2), (4, 2)]);
+    }
+
+    #[test]
+    fn not_sir0_for_short_input() {
+        for len in 0..=3 {
+            let data = &[0x53, 0x49, 0x52][..len];
+            let mut found = [0; 4];
+            found[..len].copy_from_slice(data);
+            assert!(matches!(
+                MessageBin::from_bytes(data, None),
+                Err(MessageBinReadError::NotSir0 { found: error_found }) if error_found == found
+            ));
+        }
+    }
+
+    #[test]
+    fn replace_code_point_in_several_messages() {
+        let mut message = MessageBin::from_messages(vec![
+            (1, 0, "a\u{E000}b\u{E000}".to_string()),
+            (2, 0, "nothing to replace".to_string()),
+            (3, 0, "\u{E000}".to_string()),
+        ]);
+        assert_eq!(message.replace_code_point('\u{E000}', '\u{E001}'), 3);
+        assert_eq!(message.message_by_hash(1).unwrap(), "a\u{E001}b\u{E001}");
+        assert_eq!(message.message_by_hash(2).unwrap(), "nothing to replace");
+        assert_eq!(message.message_by_hash(3).unwrap(), "\u{E001}");
+        assert_eq!(message.replace_code_point('\u{E000}', '\u{E001}'), 0);
+    }
+
+    #[test]
+    fn max_bytes_read_checked_before_reading_metadata() {
+        let message = MessageBin::from_messages(vec![(1, 0, "Hello".to_string())]);
+        let bytes = with_string_count(&message, 0xFFFF_FFF0);
+        let options = MessageBinReadOptions {
+            max_bytes_read: Some(4096),
+            ..Default::default()
+        };
+        assert!(matches!(
+            MessageBin::load_file_with_options(&mut Cursor::new(&bytes), None, &options),
+            Err(MessageBinReadError::BudgetExceeded)
+        ));
+    }
+
+    #[test]
+    fn untrusted_string_count_not_preallocated() {
+        // without a budget, the read fail at the end of the file instead of allocating for every announced string
+        let message = MessageBin::from_messages(vec![(1, 0, "Hello".to_string())]);
+        let bytes = with_string_count(&message, 0xFFFF_FFF0);
+        assert!(MessageBin::from_bytes(&bytes, None).is_err());
     }
 }
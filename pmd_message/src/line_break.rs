@@ -0,0 +1,58 @@
+/// The bits of the unknown value of a message that are interpreted as a [`LineBreakMode`]
+pub const LINE_BREAK_MODE_MASK: u32 = 0b11;
+
+/// How the text of a message is wrapped, stored in the two least significant bits of its unknown value
+/// ([`LINE_BREAK_MODE_MASK`]). The other bits are not interpreted, and are kept as is by [`LineBreakMode::apply_to_unk`].
+///
+/// This meaning of the unknown value is a supposition, not yet confirmed on the games.
+///
+/// | bits 0-1 | mode                        |
+/// |----------|-----------------------------|
+/// | `0b00`   | [`LineBreakMode::Auto`]     |
+/// | `0b01`   | [`LineBreakMode::Manual`]   |
+/// | `0b10`   | [`LineBreakMode::NoWrap`]   |
+/// | `0b11`   | [`LineBreakMode::Reserved`] |
+///
+/// ```
+/// use pmd_message::LineBreakMode;
+///
+/// let unk = 0xABCD_0001;
+/// assert_eq!(LineBreakMode::from_unk(unk), LineBreakMode::Manual);
+/// let unk = LineBreakMode::NoWrap.apply_to_unk(unk);
+/// assert_eq!(unk, 0xABCD_0002);
+/// assert_eq!(LineBreakMode::from_unk(unk), LineBreakMode::NoWrap);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineBreakMode {
+    /// The text is wrapped automatically by the game
+    Auto,
+    /// The text is only broken at the explicit line breaks
+    Manual,
+    /// The text is never broken
+    NoWrap,
+    /// A value whose meaning is unknown, kept so that every unknown value can be represented
+    Reserved,
+}
+
+impl LineBreakMode {
+    /// Read the mode from the unknown value of a message
+    pub fn from_unk(unk: u32) -> Self {
+        match unk & LINE_BREAK_MODE_MASK {
+            0 => Self::Auto,
+            1 => Self::Manual,
+            2 => Self::NoWrap,
+            _ => Self::Reserved,
+        }
+    }
+
+    /// Return the unknown value with its mode bits replaced by this mode, keeping all the other bits.
+    pub fn apply_to_unk(self, unk: u32) -> u32 {
+        let bits = match self {
+            Self::Auto => 0,
+            Self::Manual => 1,
+            Self::NoWrap => 2,
+            Self::Reserved => 3,
+        };
+        (unk & !LINE_BREAK_MODE_MASK) | bits
+    }
+}
@@ -0,0 +1,247 @@
+use crate::{
+    Decoder, MessageBin, MessageBinReadError, MessageBinReadOptions, MessageBinSir0Header,
+    MessageBinStringData, MessageBinText, TextMode,
+};
+use binread::BinReaderExt;
+use std::io::{Read, Seek, SeekFrom};
+
+/// A string read from the file, before decoding
+pub(crate) struct ReadString {
+    pub hash: u32,
+    pub unk: u32,
+    /// The offset of the string, from the start of the file
+    pub offset: u64,
+    pub units: Vec<u16>,
+    /// The number of additional null code units after the terminator, as kept by [`MessageBin::trailing_nulls`]
+    pub trailing_nulls: u16,
+}
+
+/// An iterator that read and decode the messages of a file one at a time, in the order they are stored in, as returned by
+/// [`MessageBin::load_messages_iter`](crate::MessageBin::load_messages_iter).
+///
+/// Only the metadata are kept in memory. Each item is the hash, unknown value and decoded text of a message. The iteration
+/// stop after the first error. Entries sharing the same hash are all returned.
+pub struct MessageIter<'a, T: Read + Seek> {
+    pub(crate) file: &'a mut T,
+    code_to_text: Option<&'a dyn Decoder>,
+    options: MessageBinReadOptions,
+    pub(crate) sir0_header: MessageBinSir0Header,
+    strings_data: Vec<MessageBinStringData>,
+    next_index: usize,
+    bytes_read: u64,
+    total_decoded_bytes: u64,
+}
+
+impl<'a, T: Read + Seek> MessageIter<'a, T> {
+    /// Read the Sir0 container, the header and the metadata of the file.
+    pub(crate) fn new(
+        file: &'a mut T,
+        code_to_text: Option<&'a dyn Decoder>,
+        options: &MessageBinReadOptions,
+    ) -> Result<Self, MessageBinReadError> {
+        let sir0_header = MessageBin::read_sir0_header(file, options.structure_endianness)?;
+        let strings_data = MessageBin::read_strings_data_at(file, &sir0_header, options)?;
+        let bytes_read = strings_data.len() as u64 * 12;
+        let iter = Self {
+            file,
+            code_to_text,
+            options: options.clone(),
+            sir0_header,
+            strings_data,
+            next_index: 0,
+            bytes_read,
+            total_decoded_bytes: 0,
+        };
+        iter.check_bytes_read()?;
+        Ok(iter)
+    }
+
+    /// Return whether the texts are decoded with a code table
+    pub(crate) fn text_mode(&self) -> TextMode {
+        if self.code_to_text.is_some() {
+            TextMode::Decoded
+        } else {
+            TextMode::Raw
+        }
+    }
+
+    pub(crate) fn options(&self) -> &MessageBinReadOptions {
+        &self.options
+    }
+
+    fn check_bytes_read(&self) -> Result<(), MessageBinReadError> {
+        match self.options.max_bytes_read {
+            Some(limit) if self.bytes_read > limit => Err(MessageBinReadError::BudgetExceeded),
+            _ => Ok(()),
+        }
+    }
+
+    /// Read the next string whose hash match `filter`, without decoding it. Strings that doesn't match aren't read.
+    ///
+    /// The strings after one that can't be read can still be read.
+    pub(crate) fn next_raw<F: Fn(u32) -> bool>(
+        &mut self,
+        filter: F,
+    ) -> Option<Result<ReadString, MessageBinReadError>> {
+        while self.next_index < self.strings_data.len() {
+            let index = self.next_index;
+            self.next_index += 1;
+            if filter(self.strings_data[index].string_hash) {
+                return Some(self.read_string(index));
+            };
+        }
+        None
+    }
+
+    fn read_string(&mut self, index: usize) -> Result<ReadString, MessageBinReadError> {
+        let string_block_end = self.sir0_header.string_info_pointer as u64;
+        let string_data = &self.strings_data[index];
+        let string_start = string_data.string_pointer as u64;
+        self.file.seek(SeekFrom::Start(string_start))?;
+        let text: MessageBinText = self
+            .file
+            .read_type(self.options.text_endianness.binread())?;
+        self.bytes_read += (text.text.len() as u64 + 1) * 2;
+        self.check_bytes_read()?;
+
+        // look for additional null terminators, up to the start of the next string
+        let string_end = string_start + (text.text.len() as u64 + 1) * 2;
+        let next_string_start = self.strings_data[index + 1..]
+            .iter()
+            .map(|next| next.string_pointer as u64)
+            .find(|next_start| *next_start > string_start);
+        let mut trailing_nulls: u16 = 0;
+        let available_units = next_string_start
+            .unwrap_or(string_block_end)
+            .saturating_sub(string_end)
+            / 2;
+        for _ in 0..available_units.min(u16::MAX as u64) {
+            let mut unit = [0; 2];
+            self.file.read_exact(&mut unit)?;
+            self.bytes_read += 2;
+            if unit != [0, 0] {
+                break;
+            };
+            trailing_nulls += 1;
+        }
+        self.check_bytes_read()?;
+        if next_string_start.is_none() {
+            // the last string is followed by the alignment padding of the string block, which will be rewritten anyway.
+            // Only keep the nulls that this padding can't reproduce.
+            let needed_units = string_block_end
+                .saturating_sub(3)
+                .saturating_sub(string_end)
+                .div_ceil(2);
+            trailing_nulls = trailing_nulls.min(needed_units as u16);
+        };
+
+        let string_data = &self.strings_data[index];
+        Ok(ReadString {
+            hash: string_data.string_hash,
+            unk: string_data.unk,
+            offset: string_start,
+            units: text.text.0,
+            trailing_nulls,
+        })
+    }
+
+    /// Decode a string with the code table (or as UTF-16 if there is none)
+    pub(crate) fn decode(&self, string: &ReadString) -> Result<String, MessageBinReadError> {
+        if let Some(code_to_text) = self.code_to_text {
+            code_to_text.decode(&string.units).map_err(|source| {
+                trace_event!(warn, hash = string.hash, error = %source, "can't decode a string");
+                MessageBinReadError::CantDecodeString {
+                    hash: string.hash,
+                    offset: string.offset,
+                    text: String::from_utf16_lossy(&string.units),
+                    source,
+                }
+            })
+        } else {
+            Ok(String::from_utf16_lossy(&string.units))
+        }
+    }
+
+    /// Count a decoded text against [`MessageBinReadOptions::max_total_decoded_bytes`]
+    pub(crate) fn account_decoded(&mut self, text: &str) -> Result<(), MessageBinReadError> {
+        self.total_decoded_bytes += text.len() as u64;
+        if let Some(limit) = self.options.max_total_decoded_bytes {
+            if self.total_decoded_bytes > limit {
+                return Err(MessageBinReadError::DecodedSizeExceeded { limit });
+            };
+        };
+        Ok(())
+    }
+}
+
+impl<T: Read + Seek> Iterator for MessageIter<'_, T> {
+    type Item = Result<(u32, u32, String), MessageBinReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let string = match self.next_raw(|_| true)? {
+            Ok(string) => string,
+            Err(err) => {
+                self.next_index = self.strings_data.len();
+                return Some(Err(err));
+            }
+        };
+        let result = self
+            .decode(&string)
+            .and_then(|text| self.account_decoded(&text).map(|_| text));
+        if result.is_err() {
+            self.next_index = self.strings_data.len();
+        };
+        Some(result.map(|text| (string.hash, string.unk, text)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{MessageBin, MessageBinWriter};
+    use std::io::Cursor;
+
+    /// Write the (hash, text, trailing nulls) messages in this order, and return the file
+    fn write_messages(messages: &[(u32, &str, u16)]) -> Vec<u8> {
+        let mut file = Cursor::new(Vec::new());
+        let mut writer = MessageBinWriter::new(&mut file, None, Default::default()).unwrap();
+        for (hash, text, trailing_nulls) in messages {
+            writer
+                .push_with_trailing_nulls(*hash, 0, text, *trailing_nulls)
+                .unwrap();
+        }
+        writer.finish().unwrap();
+        file.into_inner()
+    }
+
+    fn assert_identical_rewrite(bytes: &[u8]) {
+        let report =
+            MessageBin::verify_roundtrip_bytes(&mut Cursor::new(bytes), None, None).unwrap();
+        assert!(report.is_identical(), "{:?}", report);
+    }
+
+    #[test]
+    fn two_and_four_null_terminators() {
+        // "ab" and its four nulls from 16 to 24, "c" and its two nulls to 28, "d" and its four nulls to 34, then 2 bytes of padding
+        let bytes = write_messages(&[(1, "ab", 1), (2, "c", 0), (3, "d", 1)]);
+        assert_eq!(&bytes[16..24], &[b'a', 0, b'b', 0, 0, 0, 0, 0]);
+        assert_eq!(&bytes[24..28], &[b'c', 0, 0, 0]);
+        assert_eq!(&bytes[28..36], &[b'd', 0, 0, 0, 0, 0, 0, 0]);
+
+        let message = MessageBin::from_bytes(&bytes, None).unwrap();
+        assert_eq!(message.trailing_nulls(1), 1);
+        assert_eq!(message.trailing_nulls(2), 0);
+        assert_eq!(message.trailing_nulls(3), 1);
+        assert_identical_rewrite(&bytes);
+    }
+
+    #[test]
+    fn last_two_null_terminator_followed_by_padding() {
+        // "bc" and its two nulls end at 26, followed by 2 bytes of padding
+        let bytes = write_messages(&[(1, "a", 0), (2, "bc", 0)]);
+        assert_eq!(&bytes[20..28], &[b'b', 0, b'c', 0, 0, 0, 0, 0]);
+
+        let message = MessageBin::from_bytes(&bytes, None).unwrap();
+        assert_eq!(message.trailing_nulls(2), 0);
+        assert_identical_rewrite(&bytes);
+    }
+}
@@ -0,0 +1,47 @@
+/// A message with named fields, as an alternative to the `(hash, unk, text)` tuples of [`MessageBin::messages`](crate::MessageBin::messages).
+///
+/// See [`MessageBin::messages_named`](crate::MessageBin::messages_named) and [`MessageBin::from_named`](crate::MessageBin::from_named).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Message {
+    /// The hash of the message (probably the crc32 of its label)
+    pub hash: u32,
+    /// The unknown value associated with the message
+    pub unk: u32,
+    /// The content of the message
+    pub text: String,
+}
+
+impl From<(u32, u32, String)> for Message {
+    fn from((hash, unk, text): (u32, u32, String)) -> Self {
+        Self { hash, unk, text }
+    }
+}
+
+impl From<Message> for (u32, u32, String) {
+    fn from(message: Message) -> Self {
+        (message.hash, message.unk, message.text)
+    }
+}
+
+/// An iterator over the hash, unknown value and text of the messages of a [`MessageBin`](crate::MessageBin), as returned by
+/// [`MessageBin::iter`](crate::MessageBin::iter).
+pub struct Iter<'a> {
+    pub(crate) inner: std::slice::Iter<'a, (u32, u32, String)>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (u32, u32, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|(hash, unk, text)| (*hash, *unk, text.as_str()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
@@ -0,0 +1,224 @@
+use binwrite::{writer_option_new, WriterOption};
+use byteorder::{ReadBytesExt, WriteBytesExt, BE, LE};
+use std::io::{self, Read, Write};
+
+/// The byte order used to store multi-byte values in a [`MessageBin`](crate::MessageBin) file.
+///
+/// The structure and the text can use different byte orders, set in [`MessageBinReadOptions`] and [`MessageBinWriteOptions`]:
+///
+/// ```
+/// use pmd_message::{Endianness, MessageBin, MessageBinReadOptions, MessageBinWriteOptions};
+/// use std::io::Cursor;
+///
+/// let message = MessageBin::from_messages(vec![(0x1234, 1, "Hello".to_string())]);
+/// let mut file = Cursor::new(Vec::new());
+/// let write_options = MessageBinWriteOptions {
+///     structure_endianness: Endianness::Big,
+///     text_endianness: Endianness::Big,
+///     ..Default::default()
+/// };
+/// message.write_with_options(&mut file, None, &write_options).unwrap();
+///
+/// let read_options = MessageBinReadOptions {
+///     structure_endianness: Endianness::Big,
+///     text_endianness: Endianness::Big,
+///     ..Default::default()
+/// };
+/// let reloaded = MessageBin::load_file_with_options(&mut file, None, &read_options).unwrap();
+/// assert_eq!(reloaded.messages(), message.messages());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Endianness {
+    #[default]
+    Little,
+    Big,
+}
+
+impl Endianness {
+    pub(crate) fn binread(self) -> binread::Endian {
+        match self {
+            Self::Little => binread::Endian::Little,
+            Self::Big => binread::Endian::Big,
+        }
+    }
+
+    pub(crate) fn binwrite(self) -> WriterOption {
+        match self {
+            Self::Little => writer_option_new!(endian: binwrite::Endian::Little),
+            Self::Big => writer_option_new!(endian: binwrite::Endian::Big),
+        }
+    }
+
+    pub(crate) fn read_u32<R: Read>(self, file: &mut R) -> io::Result<u32> {
+        match self {
+            Self::Little => file.read_u32::<LE>(),
+            Self::Big => file.read_u32::<BE>(),
+        }
+    }
+
+    pub(crate) fn write_u32<W: Write>(self, file: &mut W, value: u32) -> io::Result<()> {
+        match self {
+            Self::Little => file.write_u32::<LE>(value),
+            Self::Big => file.write_u32::<BE>(value),
+        }
+    }
+
+    pub(crate) fn u16_to_bytes(self, value: u16) -> [u8; 2] {
+        match self {
+            Self::Little => value.to_le_bytes(),
+            Self::Big => value.to_be_bytes(),
+        }
+    }
+}
+
+/// Options used to read a [`MessageBin`](crate::MessageBin) with [`MessageBin::load_file_with_options`](crate::MessageBin::load_file_with_options).
+///
+/// The default value read a standard (fully little endian) file.
+#[derive(Debug, Clone, Default)]
+pub struct MessageBinReadOptions {
+    /// The byte order of the Sir0 container, the header and the string metadata
+    pub structure_endianness: Endianness,
+    /// The byte order of the UTF-16 code units of the messages
+    pub text_endianness: Endianness,
+    /// If set, the loading fail with [`MessageBinReadError::DecodedSizeExceeded`](crate::MessageBinReadError::DecodedSizeExceeded)
+    /// once the total size of the decoded texts (in bytes of UTF-8) exceed this limit.
+    pub max_total_decoded_bytes: Option<u64>,
+    /// If set, the loading fail with [`MessageBinReadError::BudgetExceeded`](crate::MessageBinReadError::BudgetExceeded)
+    /// once more than this number of bytes were read from the metadata and the strings. The size of the metadata, given by the
+    /// header, is checked before reading it.
+    pub max_bytes_read: Option<u64>,
+    /// If set, the loading fail with [`MessageBinReadError::BudgetExceeded`](crate::MessageBinReadError::BudgetExceeded)
+    /// before reading the metadata if the file contain more strings than this.
+    pub max_strings: Option<u64>,
+    /// If true, the loading fail with [`MessageBinReadError::DuplicateHash`](crate::MessageBinReadError::DuplicateHash) when
+    /// multiple metadata entries have the same hash. Otherwise, the last string read (in the order of the file) replace the previous ones.
+    ///
+    /// ```
+    /// use pmd_message::{MessageBin, MessageBinReadError, MessageBinReadOptions, MessageBinWriter};
+    /// use std::io::Cursor;
+    ///
+    /// let mut file = Cursor::new(Vec::new());
+    /// let mut writer = MessageBinWriter::new(&mut file, None, Default::default()).unwrap();
+    /// writer.push(0x1234, 0, "first").unwrap();
+    /// writer.push(0x1234, 0, "second").unwrap();
+    /// writer.finish().unwrap();
+    ///
+    /// let options = MessageBinReadOptions {
+    ///     reject_duplicate_hashes: true,
+    ///     ..Default::default()
+    /// };
+    /// assert!(matches!(
+    ///     MessageBin::load_file_with_options(&mut file, None, &options),
+    ///     Err(MessageBinReadError::DuplicateHash(0x1234))
+    /// ));
+    /// ```
+    pub reject_duplicate_hashes: bool,
+}
+
+/// Options used to write a [`MessageBin`](crate::MessageBin) with [`MessageBin::write_with_options`](crate::MessageBin::write_with_options).
+///
+/// The default value write a standard (fully little endian) file.
+#[derive(Debug, Clone, Default)]
+pub struct MessageBinWriteOptions {
+    /// The byte order of the Sir0 container, the header and the string metadata
+    pub structure_endianness: Endianness,
+    /// The byte order of the UTF-16 code units of the messages
+    pub text_endianness: Endianness,
+    /// The byte used to pad the end of the string block to a multiple of 4 bytes, before the metadata
+    pub padding_byte: u8,
+    /// If superior to 1, pad (with null) each string so that the next one start at a multiple of this number of bytes.
+    /// It should then be 2, 4, 8 or 16, otherwise the writing fail with [`MessageBinWriteError::InvalidStringAlignment`](crate::MessageBinWriteError::InvalidStringAlignment).
+    ///
+    /// Files that align each string this way can also be reproduced without this option, as [`MessageBin::load_file`](crate::MessageBin::load_file)
+    /// record the padding as [trailing nulls](crate::MessageBin::trailing_nulls).
+    pub string_alignment: u32,
+    /// If true, write the metadata in the order of the messages instead of sorting them by hash.
+    ///
+    /// Files written this way can't be searched with a binary search on the hashes, but the metadata of a loaded file can
+    /// be written back in the same order as the original when the strings were stored in this order.
+    ///
+    /// ```
+    /// use pmd_message::{MessageBin, MessageBinWriteOptions};
+    /// use std::io::Cursor;
+    ///
+    /// let message = MessageBin::from_messages(vec![(0x30, 0, "a".to_string()), (0x10, 0, "b".to_string())]);
+    /// let options = MessageBinWriteOptions {
+    ///     keep_metadata_order: true,
+    ///     ..Default::default()
+    /// };
+    /// let mut file = Cursor::new(Vec::new());
+    /// message.write_with_options(&mut file, None, &options).unwrap();
+    /// assert!(!MessageBin::verify_metadata_sorted(&mut file).unwrap());
+    /// ```
+    pub keep_metadata_order: bool,
+}
+
+/// What to do with a message that failed to decode, as decided by the callback passed to [`MessageBin::load_file_with_recovery`](crate::MessageBin::load_file_with_recovery).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorAction {
+    /// Don't add the message to the result
+    Skip,
+    /// Use the given text as the content of the message
+    Substitute(String),
+    /// Stop the loading, returning the error
+    Abort,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MessageBin;
+    use std::io::Cursor;
+
+    /// Write a single message "A" with the hash 0x01020304 and the unknown value 0x0A0B0C0D, check the bytes of the text
+    /// and of the metadata, then read it back.
+    fn check_hybrid(structure: Endianness, text: Endianness) {
+        let message = MessageBin::from_messages(vec![(0x0102_0304, 0x0A0B_0C0D, "A".to_string())]);
+        let mut file = Cursor::new(Vec::new());
+        let write_options = MessageBinWriteOptions {
+            structure_endianness: structure,
+            text_endianness: text,
+            ..Default::default()
+        };
+        message
+            .write_with_options(&mut file, None, &write_options)
+            .unwrap();
+        let bytes = file.get_ref();
+
+        // the string "A" and its terminator, at the start of the string block
+        let expected_text: &[u8] = match text {
+            Endianness::Little => &[0x41, 0x00, 0x00, 0x00],
+            Endianness::Big => &[0x00, 0x41, 0x00, 0x00],
+        };
+        assert_eq!(&bytes[16..20], expected_text);
+
+        // the pointer, hash and unknown value, right after the string block
+        let expected_metadata: &[u8] = match structure {
+            Endianness::Little => &[
+                0x10, 0x00, 0x00, 0x00, 0x04, 0x03, 0x02, 0x01, 0x0D, 0x0C, 0x0B, 0x0A,
+            ],
+            Endianness::Big => &[
+                0x00, 0x00, 0x00, 0x10, 0x01, 0x02, 0x03, 0x04, 0x0A, 0x0B, 0x0C, 0x0D,
+            ],
+        };
+        assert_eq!(&bytes[20..32], expected_metadata);
+
+        let read_options = MessageBinReadOptions {
+            structure_endianness: structure,
+            text_endianness: text,
+            ..Default::default()
+        };
+        let reloaded = MessageBin::load_file_with_options(&mut file, None, &read_options).unwrap();
+        assert_eq!(reloaded.messages(), message.messages());
+    }
+
+    #[test]
+    fn little_endian_structure_big_endian_text() {
+        check_hybrid(Endianness::Little, Endianness::Big);
+    }
+
+    #[test]
+    fn big_endian_structure_little_endian_text() {
+        check_hybrid(Endianness::Big, Endianness::Little);
+    }
+}
@@ -0,0 +1,37 @@
+use crate::{CodingError, Decoder};
+use std::ops::RangeInclusive;
+
+/// The range of UTF-16 code units used by the 3ds games for control codes (color, placeholder, button icon, pause...)
+pub const CONTROL_CODE_RANGE: RangeInclusive<u16> = 0xA000..=0xEFFF;
+
+/// A message as it is stored in the file, before any decoding, as returned by [`MessageBin::load_raw`](crate::MessageBin::load_raw).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawMessage {
+    /// The hash of the message (probably the crc32 of its label)
+    pub hash: u32,
+    /// The unknown value associated with the message
+    pub unk: u32,
+    /// The UTF-16 code units of the message, without the null terminator
+    pub units: Vec<u16>,
+}
+
+impl RawMessage {
+    /// Interpret the code units as UTF-16, replacing invalid sequences with the replacement character.
+    pub fn as_utf16_lossy_string(&self) -> String {
+        String::from_utf16_lossy(&self.units)
+    }
+
+    /// Return all the code units in the [`CONTROL_CODE_RANGE`], in order.
+    pub fn control_codes(&self) -> Vec<u16> {
+        self.units
+            .iter()
+            .filter(|unit| CONTROL_CODE_RANGE.contains(unit))
+            .copied()
+            .collect()
+    }
+
+    /// Decode the message to a human readable text with the given decoder (usually a code table).
+    pub fn decode(&self, code_to_text: &dyn Decoder) -> Result<String, CodingError> {
+        code_to_text.decode(&self.units)
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{MessageBinReadError, MessageBinWriteError};
+use std::io;
+use thiserror::Error;
+
+/// The number of bytes kept before and after the first difference in a [`RoundtripReport`]
+pub const ROUNDTRIP_CONTEXT_LEN: usize = 8;
+
+/// An error that may occur in [`MessageBin::verify_roundtrip_bytes`](crate::MessageBin::verify_roundtrip_bytes)
+#[derive(Error, Debug)]
+pub enum RoundtripError {
+    #[error("an input/output error occured")]
+    IOError(#[from] io::Error),
+    #[error("can't load the original file")]
+    ReadError(#[from] MessageBinReadError),
+    #[error("can't write the file back")]
+    WriteError(#[from] MessageBinWriteError),
+}
+
+/// The result of a comparison between a file and the file written back after loading it, as returned by
+/// [`MessageBin::verify_roundtrip_bytes`](crate::MessageBin::verify_roundtrip_bytes).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundtripReport {
+    /// The size of the original file, in bytes
+    pub original_size: u64,
+    /// The size of the rewritten file, in bytes
+    pub rewritten_size: u64,
+    /// The offset of the first byte that differ (or that is only present in one of the file), if any
+    pub first_difference: Option<u64>,
+    /// The bytes of the original file around the first difference, starting [`ROUNDTRIP_CONTEXT_LEN`] bytes before it (if possible)
+    pub original_context: Vec<u8>,
+    /// The bytes of the rewritten file around the first difference, starting at the same offset as `original_context`
+    pub rewritten_context: Vec<u8>,
+}
+
+impl RoundtripReport {
+    /// Compare the two files
+    pub fn compare(original: &[u8], rewritten: &[u8]) -> Self {
+        let first_difference = original
+            .iter()
+            .zip(rewritten.iter())
+            .position(|(a, b)| a != b)
+            .or_else(|| {
+                if original.len() != rewritten.len() {
+                    Some(original.len().min(rewritten.len()))
+                } else {
+                    None
+                }
+            });
+        let context = |content: &[u8]| match first_difference {
+            None => Vec::new(),
+            Some(offset) => {
+                let start = offset
+                    .saturating_sub(ROUNDTRIP_CONTEXT_LEN)
+                    .min(content.len());
+                let end = (offset + ROUNDTRIP_CONTEXT_LEN).min(content.len());
+                content[start..end].to_vec()
+            }
+        };
+        Self {
+            original_size: original.len() as u64,
+            rewritten_size: rewritten.len() as u64,
+            first_difference: first_difference.map(|offset| offset as u64),
+            original_context: context(original),
+            rewritten_context: context(rewritten),
+        }
+    }
+
+    /// Return true if the rewritten file is identical to the original one
+    pub fn is_identical(&self) -> bool {
+        self.first_difference.is_none()
+    }
+}
@@ -0,0 +1,141 @@
+/// A part of a decoded message, as returned by [`segment_for_translation`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    /// Human text, that can be translated. Escape sequences (`\[` and `\\`) are kept as is.
+    Translatable(String),
+    /// A placeholder (like `[CS:A]`), including its brackets, that should be kept untouched.
+    Protected(String),
+}
+
+impl Segment {
+    /// Return the name of a protected placeholder, the part before the first `:` (`CS` for `[CS:A]`), or `None` for translatable text.
+    pub fn placeholder_name(&self) -> Option<&str> {
+        match self {
+            Self::Protected(placeholder) => {
+                let inner = placeholder.trim_start_matches('[').trim_end_matches(']');
+                Some(inner.split(':').next().unwrap_or(inner))
+            }
+            Self::Translatable(_) => None,
+        }
+    }
+}
+
+/// Split a message decoded by a [`CodeToText`](pmd_code_table::CodeToText) into translatable text and protected placeholders.
+///
+/// An unclosed placeholder at the end of the text is considered protected. [`reassemble`] reverse this operation.
+pub fn segment_for_translation(text: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    let mut chars = text.chars();
+    while let Some(chara) = chars.next() {
+        match chara {
+            '\\' => {
+                current.push(chara);
+                if let Some(escaped) = chars.next() {
+                    current.push(escaped);
+                }
+            }
+            '[' => {
+                if !current.is_empty() {
+                    segments.push(Segment::Translatable(current));
+                }
+                let mut placeholder = String::from('[');
+                for placeholder_char in chars.by_ref() {
+                    placeholder.push(placeholder_char);
+                    if placeholder_char == ']' {
+                        break;
+                    }
+                }
+                segments.push(Segment::Protected(placeholder));
+                current = String::new();
+            }
+            other => current.push(other),
+        }
+    }
+    if !current.is_empty() {
+        segments.push(Segment::Translatable(current));
+    }
+    segments
+}
+
+/// Join segments back into a single text.
+pub fn reassemble(segments: &[Segment]) -> String {
+    let mut result = String::new();
+    for segment in segments {
+        match segment {
+            Segment::Translatable(text) | Segment::Protected(text) => result.push_str(text),
+        }
+    }
+    result
+}
+
+/// Split a decoded message into pages, at each occurrence of `page_break`, the placeholder the code table decode the
+/// page break code to. The page breaks are not included in the pages.
+///
+/// A page break whose `[` is escaped (`\[`) doesn't match.
+///
+/// Every page break end the page before it, even an empty one: a page break at the start of the text give an empty first
+/// page, and two consecutive page breaks give an empty page between them. The only exception is a page break at the end of
+/// the text, which isn't followed by an empty last page. So a text made of a single page break give one empty page, and an
+/// empty text give no page.
+///
+/// ```
+/// use pmd_message::split_pages;
+///
+/// let text = "Hello![K]How are you?[CS:A]\\[K][K]";
+/// assert_eq!(split_pages(text, "[K]"), vec!["Hello!", "How are you?[CS:A]\\[K]"]);
+/// assert_eq!(split_pages("One page", "[K]"), vec!["One page"]);
+/// assert!(split_pages("", "[K]").is_empty());
+/// ```
+pub fn split_pages<'a>(text: &'a str, page_break: &str) -> Vec<&'a str> {
+    let mut pages = Vec::new();
+    let mut page_start = 0;
+    let mut position = 0;
+    while let Some(chara) = text[position..].chars().next() {
+        if chara == '\\' {
+            // skip the escaped character too
+            position += 1 + text[position + 1..]
+                .chars()
+                .next()
+                .map_or(0, char::len_utf8);
+        } else if !page_break.is_empty() && text[position..].starts_with(page_break) {
+            pages.push(&text[page_start..position]);
+            position += page_break.len();
+            page_start = position;
+        } else {
+            position += chara.len_utf8();
+        }
+    }
+    if page_start < text.len() {
+        pages.push(&text[page_start..]);
+    }
+    pages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::split_pages;
+
+    #[test]
+    fn split_pages_leading_break() {
+        assert_eq!(
+            split_pages("[K]first[K]second", "[K]"),
+            vec!["", "first", "second"]
+        );
+    }
+
+    #[test]
+    fn split_pages_consecutive_breaks() {
+        assert_eq!(
+            split_pages("first[K][K]second", "[K]"),
+            vec!["first", "", "second"]
+        );
+        assert_eq!(split_pages("first[K][K]", "[K]"), vec!["first", ""]);
+    }
+
+    #[test]
+    fn split_pages_lone_break() {
+        assert_eq!(split_pages("[K]", "[K]"), vec![""]);
+        assert_eq!(split_pages("only[K]", "[K]"), vec!["only"]);
+    }
+}
@@ -0,0 +1,69 @@
+use crate::MessageBin;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+
+/// The serialized form of a [`MessageBin`]. The index by hash isn't stored, and is rebuilt on deserialization.
+#[derive(Serialize)]
+struct SerializedMessageBinRef<'a> {
+    messages: &'a [(u32, u32, String)],
+    trailing_nulls: &'a BTreeMap<u32, u16>,
+}
+
+#[derive(Deserialize)]
+struct SerializedMessageBin {
+    messages: Vec<(u32, u32, String)>,
+    #[serde(default)]
+    trailing_nulls: BTreeMap<u32, u16>,
+}
+
+impl Serialize for MessageBin {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializedMessageBinRef {
+            messages: &self.message,
+            trailing_nulls: &self.trailing_nulls,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for MessageBin {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let serialized = SerializedMessageBin::deserialize(deserializer)?;
+        let mut message_bin = MessageBin::from_messages(serialized.messages);
+        for (hash, count) in serialized.trailing_nulls {
+            if message_bin.hash_to_id.contains_key(&hash) {
+                message_bin.set_trailing_nulls(hash, count);
+            }
+        }
+        Ok(message_bin)
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use crate::MessageBin;
+
+    #[test]
+    fn json_roundtrip() {
+        let mut message = MessageBin::from_messages(vec![
+            (0x30, 1, "first".to_string()),
+            (0x10, 2, "second".to_string()),
+            (0x20, 3, String::new()),
+        ]);
+        message.set_trailing_nulls(0x10, 2);
+
+        let json = serde_json::to_string(&message).unwrap();
+        let deserialized: MessageBin = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(deserialized.messages(), message.messages());
+        for hash in [0x10, 0x20, 0x30] {
+            assert_eq!(
+                deserialized.trailing_nulls(hash),
+                message.trailing_nulls(hash)
+            );
+        }
+        assert_eq!(deserialized.message_by_hash(0x10).unwrap(), "second");
+        assert_eq!(deserialized.unk_by_hash(0x30), Some(1));
+        assert_eq!(deserialized.message_by_hash(0x40), None);
+    }
+}
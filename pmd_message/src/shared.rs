@@ -0,0 +1,38 @@
+use crate::MessageBin;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Return the hashes present in all the given files. Return an empty set if there are no files.
+pub fn common_hashes(files: &[MessageBin]) -> BTreeSet<u32> {
+    let (first, others) = match files.split_first() {
+        Some(split) => split,
+        None => return BTreeSet::new(),
+    };
+    first
+        .messages()
+        .iter()
+        .map(|(hash, _, _)| *hash)
+        .filter(|hash| {
+            others
+                .iter()
+                .all(|other| other.message_by_hash(*hash).is_some())
+        })
+        .collect()
+}
+
+/// Return the messages present with the same text in all the given files, indexed by hash.
+pub fn shared_texts(files: &[MessageBin]) -> BTreeMap<u32, &str> {
+    let (first, others) = match files.split_first() {
+        Some(split) => split,
+        None => return BTreeMap::new(),
+    };
+    first
+        .messages()
+        .iter()
+        .filter(|(hash, _, text)| {
+            others
+                .iter()
+                .all(|other| other.message_by_hash(*hash) == Some(text))
+        })
+        .map(|(hash, _, text)| (*hash, text.as_str()))
+        .collect()
+}
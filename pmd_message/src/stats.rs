@@ -0,0 +1,18 @@
+use std::collections::BTreeMap;
+
+/// Statistics on the messages of a [`MessageBin`](crate::MessageBin), as returned by [`MessageBin::stats`](crate::MessageBin::stats).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MessageBinStats {
+    /// The number of messages
+    pub message_count: usize,
+    /// The number of messages with an empty text
+    pub empty_count: usize,
+    /// The total number of characters of the texts (placeholders included)
+    pub char_count: usize,
+    /// The number of messages using each placeholder, indexed by placeholder name (like `CS` for `[CS:A]`)
+    pub placeholder_messages: BTreeMap<String, usize>,
+    /// The smallest hash, if there is any message
+    pub min_hash: Option<u32>,
+    /// The biggest hash, if there is any message
+    pub max_hash: Option<u32>,
+}
@@ -0,0 +1,396 @@
+use crate::Encoder;
+use crate::{Endianness, MessageBinStringData, MessageBinWriteError, MessageBinWriteOptions};
+use binwrite::BinWrite;
+use pmd_sir0::{write_sir0_footer, write_sir0_header};
+use std::{
+    convert::TryInto,
+    io::{Seek, SeekFrom, Write},
+};
+
+/// Write a [`MessageBin`](crate::MessageBin) file one message at a time, without requiring all of them to be in memory.
+///
+/// The text of each message is encoded and written as soon as it is pushed. Only the metadata (pointer, hash and unk value)
+/// of each message is kept until [`MessageBinWriter::finish`] write it with the Sir0 container.
+///
+/// Each hash should only be pushed once.
+pub struct MessageBinWriter<'a, T: Seek + Write> {
+    file: &'a mut T,
+    text_to_code: Option<&'a dyn Encoder>,
+    options: MessageBinWriteOptions,
+    strings_data: Vec<MessageBinStringData>,
+    text_current_offset: u32,
+}
+
+impl<'a, T: Seek + Write> MessageBinWriter<'a, T> {
+    /// Create a new writer, and reserve the space for the Sir0 header at the start of the file.
+    ///
+    /// Fail with [`MessageBinWriteError::InvalidStringAlignment`] if [`MessageBinWriteOptions::string_alignment`] can't be respected.
+    pub fn new(
+        file: &'a mut T,
+        text_to_code: Option<&'a dyn Encoder>,
+        options: MessageBinWriteOptions,
+    ) -> Result<Self, MessageBinWriteError> {
+        check_string_alignment(options.string_alignment)?;
+        file.write_all(&[0; 16])?; //sir0 header and padding
+        Ok(Self {
+            file,
+            text_to_code,
+            options,
+            strings_data: Vec::new(),
+            text_current_offset: 16,
+        })
+    }
+
+    /// Encode and write the text of a message.
+    pub fn push(&mut self, hash: u32, unk: u32, text: &str) -> Result<(), MessageBinWriteError> {
+        self.push_with_trailing_nulls(hash, unk, text, 0)
+    }
+
+    /// Encode and write the text of a message, followed by `trailing_nulls` null code units in addition to the terminator.
+    pub fn push_with_trailing_nulls(
+        &mut self,
+        hash: u32,
+        unk: u32,
+        text: &str,
+        trailing_nulls: u16,
+    ) -> Result<(), MessageBinWriteError> {
+        let text_to_write = encode_text(hash, text, self.text_to_code)?;
+        if let Some(position) = text_to_write.iter().position(|unit| *unit == 0) {
+            return Err(MessageBinWriteError::EmbeddedNull { hash, position });
+        };
+        let mut binary_text_to_write = text_to_write
+            .iter()
+            .flat_map(|v| self.options.text_endianness.u16_to_bytes(*v).to_vec())
+            .collect::<Vec<u8>>();
+        binary_text_to_write.resize(
+            binary_text_to_write.len() + (trailing_nulls as usize + 1) * 2,
+            0,
+        );
+        if self.options.string_alignment > 1 {
+            let alignment = self.options.string_alignment as usize;
+            let string_end = self.text_current_offset as usize + binary_text_to_write.len();
+            binary_text_to_write.resize(
+                binary_text_to_write.len() + (alignment - string_end % alignment) % alignment,
+                0,
+            );
+        };
+        self.file.write_all(&binary_text_to_write)?;
+        self.strings_data.push(MessageBinStringData {
+            string_pointer: self.text_current_offset,
+            string_hash: hash,
+            unk,
+        });
+
+        self.text_current_offset = self
+            .text_current_offset
+            .checked_add(binary_text_to_write.len().try_into()?)
+            .map_or_else(|| Err(MessageBinWriteError::Overflow), Ok)?;
+        Ok(())
+    }
+
+    /// Write the metadata of the pushed messages and the Sir0 container, completing the file.
+    ///
+    /// The string block is padded to 4 bytes (with [`MessageBinWriteOptions::padding_byte`]), and the header to 16 bytes (with null),
+    /// as in the files of the games. The reading doesn't depend on those paddings, as it only follow absolute offsets.
+    ///
+    /// ```
+    /// use pmd_message::{MessageBin, MessageBinReadOptions, MessageBinWriter};
+    /// use std::io::Cursor;
+    ///
+    /// let mut file = Cursor::new(Vec::new());
+    /// let mut writer = MessageBinWriter::new(&mut file, None, Default::default()).unwrap();
+    /// writer.push(1, 0, "a").unwrap(); // 4 bytes
+    /// writer.push(2, 0, "ab").unwrap(); // 6 bytes
+    /// writer.push(3, 0, "abc").unwrap(); // 8 bytes
+    /// writer.finish().unwrap();
+    ///
+    /// let offsets = MessageBin::string_offsets(&mut file, &MessageBinReadOptions::default()).unwrap();
+    /// let starts: Vec<u64> = offsets.iter().map(|string| string.offset).collect();
+    /// assert_eq!(starts, vec![16, 20, 26]);
+    /// let reloaded = MessageBin::load_file(&mut file, None).unwrap();
+    /// assert_eq!(reloaded.message_by_hash(3).unwrap(), "abc");
+    /// ```
+    ///
+    /// The output is stable. This is the complete file written for two messages:
+    ///
+    /// ```
+    /// use pmd_message::MessageBin;
+    ///
+    /// let message = MessageBin::from_messages(vec![(0x20, 1, "b".to_string()), (0x10, 2, "a".to_string())]);
+    /// assert_eq!(
+    ///     message.to_bytes(None).unwrap(),
+    ///     vec![
+    ///         0x53, 0x49, 0x52, 0x30, 0x30, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sir0 header
+    ///         0x62, 0x00, 0x00, 0x00, 0x61, 0x00, 0x00, 0x00, // strings
+    ///         0x14, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, // metadata, sorted by hash
+    ///         0x10, 0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+    ///         0x02, 0x00, 0x00, 0x00, 0x18, 0x00, 0x00, 0x00, // header
+    ///         0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // padding
+    ///         0x04, 0x04, 0x10, 0x0C, 0x10, // sir0 footer
+    ///     ]
+    /// );
+    /// ```
+    pub fn finish(self) -> Result<(), MessageBinWriteError> {
+        self.finish_and_len().map(|_| ())
+    }
+
+    /// Like [`MessageBinWriter::finish`], but return the length of the written file, in bytes.
+    pub fn finish_and_len(mut self) -> Result<u64, MessageBinWriteError> {
+        let file = self.file;
+        trace_event!(
+            debug,
+            count = self.strings_data.len(),
+            "string block written"
+        );
+        let structure_endianness = self.options.structure_endianness;
+        let mut sir0_offsets: Vec<u32> = vec![4, 8];
+
+        // padding of 4, computed from the real position so it can't drift from the string pointers
+        let string_end = file.stream_position()?;
+        if string_end != self.text_current_offset as u64 {
+            return Err(MessageBinWriteError::UnexpectedPosition {
+                expected: self.text_current_offset as u64,
+                found: string_end,
+            });
+        };
+        if string_end % 4 != 0 {
+            let nb_to_write = 4 - string_end % 4;
+            file.write_all(&vec![self.options.padding_byte; nb_to_write as usize])?;
+        }
+
+        if !self.options.keep_metadata_order {
+            self.strings_data.sort_unstable_by_key(|e| e.string_hash);
+        };
+
+        // each entry is written directly, without serializing all of them in a temporary buffer first
+        let string_meta_position: u32 = file.stream_position()?.try_into()?;
+        for (count, string_data) in self.strings_data.iter().enumerate() {
+            string_data.write_options(file, &structure_endianness.binwrite())?;
+            sir0_offsets.push(string_meta_position + (count as u32) * 12);
+        }
+        trace_event!(debug, string_meta_position, "string metadata written");
+
+        let number_of_strings: u32 = self.strings_data.len().try_into()?;
+        let string_relative_end_offset = number_of_strings
+            .checked_mul(12)
+            .map_or_else(|| Err(MessageBinWriteError::Overflow), Ok)?;
+        let string_absolute_end_offset = string_meta_position
+            .checked_add(string_relative_end_offset)
+            .map_or_else(|| Err(MessageBinWriteError::Overflow), Ok)?;
+        sir0_offsets.push(
+            string_absolute_end_offset
+                .checked_add(4)
+                .map_or_else(|| Err(MessageBinWriteError::Overflow), Ok)?,
+        );
+
+        let sir0_header_position = file.stream_position()?;
+        structure_endianness.write_u32(file, number_of_strings)?;
+        structure_endianness.write_u32(file, string_meta_position)?;
+
+        // padding of 16, between the header and the sir0 footer
+        let current_position = file.stream_position()?;
+        if current_position % 16 != 0 {
+            file.write_all(&vec![0; 16 - (current_position as usize % 16)])?;
+        };
+
+        let sir0_footer_position = file.stream_position()?;
+
+        write_sir0_footer(file, &sir0_offsets)?;
+        trace_event!(debug, sir0_footer_position, "sir0 footer written");
+        let file_len = file.stream_position()?;
+
+        file.seek(SeekFrom::Start(0))?;
+        match structure_endianness {
+            Endianness::Little => write_sir0_header(
+                file,
+                sir0_header_position.try_into()?,
+                sir0_footer_position.try_into()?,
+            )?,
+            Endianness::Big => {
+                file.write_all(b"SIR0")?;
+                structure_endianness.write_u32(file, sir0_header_position.try_into()?)?;
+                structure_endianness.write_u32(file, sir0_footer_position.try_into()?)?;
+            }
+        };
+        Ok(file_len)
+    }
+}
+
+/// Check that [`MessageBinWriteOptions::string_alignment`] can be respected
+pub(crate) fn check_string_alignment(alignment: u32) -> Result<(), MessageBinWriteError> {
+    // strings are made of 2 bytes code units, and the first one always start at 16
+    if alignment > 16 || (alignment > 1 && !alignment.is_power_of_two()) {
+        return Err(MessageBinWriteError::InvalidStringAlignment(alignment));
+    };
+    Ok(())
+}
+
+/// Encode a text to the UTF-16 code units stored in the file (without the null terminator)
+pub(crate) fn encode_text(
+    hash: u32,
+    text: &str,
+    text_to_code: Option<&dyn Encoder>,
+) -> Result<Vec<u16>, MessageBinWriteError> {
+    if let Some(text_to_code) = text_to_code {
+        text_to_code
+            .encode(text)
+            .map_err(|source| MessageBinWriteError::CantEncodeText {
+                hash,
+                text: text.to_string(),
+                source,
+            })
+    } else {
+        Ok(text.encode_utf16().collect())
+    }
+}
+
+/// Compute the size of the file [`MessageBinWriter`] produce for `string_count` messages whose texts take `string_block_len` bytes (including their terminators).
+pub(crate) fn written_file_size(
+    string_block_len: u64,
+    string_count: u64,
+) -> Result<u64, MessageBinWriteError> {
+    let overflow = || MessageBinWriteError::Overflow;
+    let string_end = string_block_len.checked_add(16).ok_or_else(overflow)?;
+    let string_meta_position = string_end.div_ceil(4) * 4;
+    let sir0_header_position = string_count
+        .checked_mul(12)
+        .and_then(|len| len.checked_add(string_meta_position))
+        .ok_or_else(overflow)?;
+    let sir0_footer_position = (sir0_header_position + 8).div_ceil(16) * 16;
+
+    let mut sir0_offsets: Vec<u32> = vec![4, 8];
+    let string_meta_position: u32 = string_meta_position.try_into()?;
+    for count in 0..string_count {
+        let count: u32 = count.try_into()?;
+        sir0_offsets.push(string_meta_position + count * 12);
+    }
+    sir0_offsets.push((sir0_header_position + 4).try_into()?);
+    let mut footer = Vec::new();
+    write_sir0_footer(&mut footer, &sir0_offsets)?;
+
+    Ok(sir0_footer_position + footer.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        MessageBin, MessageBinReadOptions, MessageBinWriteError, MessageBinWriteOptions,
+        MessageBinWriter,
+    };
+    use std::io::Cursor;
+
+    #[test]
+    fn padding_byte_after_string_block() {
+        // "ab" and its terminator end at 22, so 2 bytes of padding are needed before the metadata
+        let message = MessageBin::from_messages(vec![(1, 0, "ab".to_string())]);
+        let mut file = Cursor::new(Vec::new());
+        let options = MessageBinWriteOptions {
+            padding_byte: 0xFF,
+            ..Default::default()
+        };
+        message
+            .write_with_options(&mut file, None, &options)
+            .unwrap();
+        let bytes = file.into_inner();
+        assert_eq!(&bytes[16..22], &[b'a', 0, b'b', 0, 0, 0]);
+        assert_eq!(&bytes[22..24], &[0xFF, 0xFF]);
+        // the metadata start right after, with the pointer to the string
+        assert_eq!(&bytes[24..28], &[0x10, 0, 0, 0]);
+        assert_eq!(
+            MessageBin::from_bytes(&bytes, None)
+                .unwrap()
+                .message_by_hash(1)
+                .unwrap(),
+            "ab"
+        );
+    }
+
+    #[test]
+    fn string_alignment() {
+        let message = MessageBin::from_messages(vec![
+            (1, 0, "a".to_string()),
+            (2, 0, "abc".to_string()),
+            (3, 0, "ab".to_string()),
+        ]);
+        let options = MessageBinWriteOptions {
+            string_alignment: 8,
+            ..Default::default()
+        };
+        let mut file = Cursor::new(Vec::new());
+        message
+            .write_with_options(&mut file, None, &options)
+            .unwrap();
+
+        let offsets =
+            MessageBin::string_offsets(&mut file, &MessageBinReadOptions::default()).unwrap();
+        let starts: Vec<u64> = offsets.iter().map(|string| string.offset).collect();
+        assert_eq!(starts, vec![16, 24, 32]);
+        assert_eq!(
+            MessageBin::check_string_layout(&mut file, &MessageBinReadOptions::default()).unwrap(),
+            Vec::new()
+        );
+
+        // the padding is kept as trailing nulls, so the file can be written back identically without the option
+        let reloaded = MessageBin::load_file(&mut file, None).unwrap();
+        assert_eq!(reloaded.messages(), message.messages());
+        assert_eq!(reloaded.to_bytes(None).unwrap(), file.into_inner());
+    }
+
+    #[test]
+    fn invalid_string_alignment() {
+        let message = MessageBin::from_messages(vec![(1, 0, "a".to_string())]);
+        for string_alignment in [3, 6, 32] {
+            let options = MessageBinWriteOptions {
+                string_alignment,
+                ..Default::default()
+            };
+            assert!(matches!(
+                message.write_with_options(&mut Cursor::new(Vec::new()), None, &options),
+                Err(MessageBinWriteError::InvalidStringAlignment(alignment)) if alignment == string_alignment
+            ));
+        }
+    }
+
+    #[test]
+    fn embedded_null_rejected() {
+        let message = MessageBin::from_messages(vec![
+            (1, 0, "fine".to_string()),
+            (2, 0, "cut\0here".to_string()),
+        ]);
+        assert!(matches!(
+            message.to_bytes(None),
+            Err(MessageBinWriteError::EmbeddedNull {
+                hash: 2,
+                position: 3
+            })
+        ));
+
+        let mut file = Cursor::new(Vec::new());
+        let mut writer = MessageBinWriter::new(&mut file, None, Default::default()).unwrap();
+        assert!(matches!(
+            writer.push(3, 0, "\0"),
+            Err(MessageBinWriteError::EmbeddedNull {
+                hash: 3,
+                position: 0
+            })
+        ));
+    }
+
+    #[test]
+    fn header_padding_roundtrip() {
+        let original: Vec<u8> = vec![
+            0x53, 0x49, 0x52, 0x30, 0x30, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, // sir0 header
+            0x62, 0x00, 0x00, 0x00, 0x61, 0x00, 0x00, 0x00, // strings
+            0x14, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00,
+            0x00, // metadata
+            0x10, 0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00,
+            0x00, 0x00, 0x18, 0x00, 0x00, 0x00, // header, at 0x30
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // padding to 16 bytes
+            0x04, 0x04, 0x10, 0x0C, 0x10, // sir0 footer, at 0x40
+        ];
+        let report =
+            MessageBin::verify_roundtrip_bytes(&mut Cursor::new(&original), None, None).unwrap();
+        assert!(report.is_identical(), "{:?}", report);
+    }
+}